@@ -0,0 +1,122 @@
+use std::time::{Duration, Instant};
+
+use chess_rs::{negamax_move, negamax_move_until, parse_epd, EpdRecord, Move, ZobristTable};
+use eyre::{eyre, Result};
+
+const USAGE: &str = "usage: epd [--depth <plies> | --time <seconds>] <file>";
+
+const DEFAULT_DEPTH: u8 = 5;
+const MAX_DEPTH: u8 = 64;
+/// Estimated growth of the search time per additional ply
+const BRANCHING_RATIO: f64 = 4.0;
+
+enum Budget {
+    Depth(u8),
+    /// Deepens until the time is used up, a depth not finished in time is abandoned for the last completed one
+    Time(Duration),
+}
+
+/// Runs the engine over a test suite like WAC or STS and reports which positions it solves
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+
+    let mut budget = Budget::Depth(DEFAULT_DEPTH);
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => {
+                let depth = args.next().and_then(|depth| depth.parse().ok()).filter(|depth| (1..=MAX_DEPTH).contains(depth));
+                budget = Budget::Depth(depth.ok_or_else(|| eyre!("expected a depth between 1 and {MAX_DEPTH}\n{USAGE}"))?);
+            }
+            "--time" => {
+                let seconds = args.next().and_then(|seconds| seconds.parse().ok()).filter(|seconds: &f64| *seconds > 0.0);
+                budget = Budget::Time(Duration::from_secs_f64(seconds.ok_or_else(|| eyre!("expected a positive number of seconds\n{USAGE}"))?));
+            }
+            _ if arg.starts_with("--") => return Err(eyre!("unknown option {arg}\n{USAGE}")),
+            _ => path = Some(arg),
+        }
+    }
+
+    let path = path.ok_or_else(|| eyre!(USAGE))?;
+    let zobrist_table = ZobristTable::default();
+    let records = parse_epd(&std::fs::read_to_string(&path)?, &zobrist_table)
+        .map_err(|(line, error)| eyre!("{path}:{line}: {error}"))?;
+
+    let start = Instant::now();
+    let mut solved = 0;
+    let mut failed = 0;
+    let mut skipped = 0;
+
+    for (index, record) in records.iter().enumerate() {
+        let name = record.id.clone().unwrap_or_else(|| format!("#{}", index + 1));
+
+        if record.best_moves.is_empty() && record.avoid_moves.is_empty() {
+            println!("{name}: skipped, no bm or am");
+            skipped += 1;
+            continue;
+        }
+
+        let Some((r#move, depth)) = search(record, &budget, &zobrist_table) else {
+            println!("{name}: skipped, no legal moves");
+            skipped += 1;
+            continue;
+        };
+
+        let san = record.board.move_to_san(r#move, &zobrist_table);
+        if record.is_solved_by(r#move) {
+            println!("{name}: solved with {san} at depth {depth}");
+            solved += 1;
+        } else {
+            println!("{name}: FAILED, played {san} at depth {depth}, expected {}", expectation(record, &zobrist_table));
+            failed += 1;
+        }
+    }
+
+    println!();
+    println!("Solved: {solved}/{}", solved + failed);
+    println!("Failed: {failed}");
+    println!("Skipped: {skipped}");
+    println!("Time: {:.3}s", start.elapsed().as_secs_f64());
+
+    Ok(())
+}
+
+/// The engine's move and the depth it was found at
+fn search(record: &EpdRecord, budget: &Budget, zobrist_table: &ZobristTable) -> Option<(Move, u8)> {
+    match *budget {
+        Budget::Depth(depth) => negamax_move(record.board.clone(), depth, zobrist_table).map(|(r#move, _)| (r#move, depth)),
+        Budget::Time(time) => {
+            let deadline = Instant::now() + time;
+
+            // The first depth is always finished, to have a move at all
+            let mut iteration_start = Instant::now();
+            let mut best = (negamax_move(record.board.clone(), 1, zobrist_table)?.0, 1);
+
+            for depth in 2..=MAX_DEPTH {
+                // Don't start a depth that can't be expected to finish
+                let last_iteration = iteration_start.elapsed();
+                if last_iteration.mul_f64(BRANCHING_RATIO) > deadline.saturating_duration_since(Instant::now()) {
+                    break;
+                }
+
+                iteration_start = Instant::now();
+                match negamax_move_until(record.board.clone(), depth, zobrist_table, deadline) {
+                    Some((r#move, _)) => best = (r#move, depth),
+                    None => break,
+                }
+            }
+
+            Some(best)
+        }
+    }
+}
+
+fn expectation(record: &EpdRecord, zobrist_table: &ZobristTable) -> String {
+    let to_san = |moves: &[Move]| moves.iter().map(|r#move| record.board.move_to_san(*r#move, zobrist_table)).collect::<Vec<_>>().join(" ");
+
+    match (record.best_moves.is_empty(), record.avoid_moves.is_empty()) {
+        (false, true) => to_san(&record.best_moves),
+        (true, false) => format!("not {}", to_san(&record.avoid_moves)),
+        _ => format!("{}, not {}", to_san(&record.best_moves), to_san(&record.avoid_moves)),
+    }
+}
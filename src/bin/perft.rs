@@ -0,0 +1,57 @@
+use std::time::Instant;
+
+use chess_rs::{Board, ZobristTable, START_POSITION_FEN};
+use eyre::{eyre, Result};
+
+const USAGE: &str = "usage: perft [--single-threaded | --cache] <depth> [fen]";
+
+/// Prints the node count of every root move followed by the total, in the format of common engines' divide output
+fn main() -> Result<()> {
+    let (flags, mut args): (Vec<String>, Vec<String>) = std::env::args().skip(1).partition(|arg| arg.starts_with("--"));
+
+    let mut single_threaded = false;
+    let mut use_cache = false;
+    for flag in flags {
+        match flag.as_str() {
+            "--single-threaded" => single_threaded = true,
+            "--cache" => use_cache = true,
+            _ => return Err(eyre!("unknown option {flag}\n{USAGE}")),
+        }
+    }
+
+    // The single-threaded divide has no cache, don't silently ignore the flag
+    if args.is_empty() || (single_threaded && use_cache) {
+        return Err(eyre!(USAGE));
+    }
+    let depth: u8 = args.remove(0).parse().map_err(|e| eyre!("invalid depth: {e}\n{USAGE}"))?;
+    let fen = args.join(" ");
+    let fen = if fen.is_empty() { START_POSITION_FEN } else { &fen };
+
+    let board = Board::from_fen(fen)?;
+    let zobrist_table = ZobristTable::default();
+
+    // Build the lazily initialized attack tables before starting the clock
+    board.generate_legal_moves(board.next_player);
+
+    let start = Instant::now();
+    let mut divided = if single_threaded {
+        board.divide(depth, &zobrist_table)
+    } else {
+        board.divide_parallel(depth, &zobrist_table, use_cache)
+    };
+    let elapsed = start.elapsed();
+
+    divided.sort_by_key(|(r#move, _)| board.move_to_uci(*r#move));
+    for (r#move, nodes) in &divided {
+        println!("{}: {nodes}", board.move_to_uci(*r#move));
+    }
+
+    // Depth 0 has no root moves to divide, but still counts the root position itself
+    let total: u64 = if depth == 0 { 1 } else { divided.iter().map(|(_, nodes)| nodes).sum() };
+    println!();
+    println!("Nodes searched: {total}");
+    println!("Time: {:.3}s", elapsed.as_secs_f64());
+    println!("Nodes/second: {:.0}", total as f64 / elapsed.as_secs_f64());
+
+    Ok(())
+}
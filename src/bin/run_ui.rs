@@ -1,16 +1,13 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use chess::visualizer::ChessVisualizer;
+use chess_rs::ChessVisualizer;
 use egui::{Style, Visuals};
 use eyre::Result;
-use eyre::{eyre, Context};
-use std::io::Write;
+use eyre::eyre;
 use eframe::AppCreator;
 
-#[path = "../chess/mod.rs"]
-mod chess;
-
 const NATIVE_SEARCH_DEPTH: u8 = 6;
+#[cfg(target_arch = "wasm32")]
 const WEB_SEARCH_DEPTH: u8 = 5;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -0,0 +1,96 @@
+use crate::chess::board::{Color, BOARD_SIZE_X, BOARD_SIZE_Y};
+use crate::chess::r#move::{KING_OFFSETS, KNIGHT_OFFSETS};
+use crate::chess::vector::Vector;
+
+pub use crate::chess::magic::{bishop_attacks, rook_attacks};
+
+/// One bit per square, indexed by `Vector::index`, i.e. bit 0 is a8 and bit 63 is h1
+pub type Bitboard = u64;
+
+pub const LIGHT_SQUARES: Bitboard = 0xaa55_aa55_aa55_aa55; // a8 is light
+pub const DARK_SQUARES: Bitboard = !LIGHT_SQUARES;
+
+pub const KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(&KNIGHT_OFFSETS);
+pub const KING_ATTACKS: [Bitboard; 64] = leaper_attacks(&KING_OFFSETS);
+
+/// Squares attacked by a pawn of the given color (indexed by `Color::zobrist_index`) on the square
+pub const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_attacks(&[Vector(-1, -1), Vector(1, -1)]),
+    leaper_attacks(&[Vector(-1, 1), Vector(1, 1)]),
+];
+
+#[inline]
+pub fn square_bit(coord: Vector) -> Bitboard {
+    1 << coord.index()
+}
+
+#[inline]
+pub fn pawn_attacks(coord: Vector, color: Color) -> Bitboard {
+    PAWN_ATTACKS[color.zobrist_index()][coord.index()]
+}
+
+/// Squares between two squares on the same rank, both included
+pub fn rank_span(from: Vector, to: Vector) -> Bitboard {
+    debug_assert_eq!(from.1, to.1);
+    (from.0.min(to.0)..=from.0.max(to.0)).fold(0, |span, x| span | square_bit(Vector(x, from.1)))
+}
+
+const fn leaper_attacks(offsets: &[Vector]) -> [Bitboard; 64] {
+    let mut attacks = [0; 64];
+
+    let mut index = 0;
+    while index < 64 {
+        let x = index as i8 % BOARD_SIZE_X;
+        let y = index as i8 / BOARD_SIZE_X;
+
+        let mut i = 0;
+        while i < offsets.len() {
+            let dst_x = x + offsets[i].0;
+            let dst_y = y + offsets[i].1;
+            if dst_x >= 0 && dst_x < BOARD_SIZE_X && dst_y >= 0 && dst_y < BOARD_SIZE_Y {
+                attacks[index] |= 1 << (dst_y * BOARD_SIZE_X + dst_x);
+            }
+            i += 1;
+        }
+
+        index += 1;
+    }
+
+    attacks
+}
+
+/// Iterates the coordinates of all set bits, lowest index first
+pub struct BitboardIter(pub Bitboard);
+
+impl Iterator for BitboardIter {
+    type Item = Vector;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let index = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1; // clear lowest bit
+
+        Some(Vector::from_index(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attack_tables() {
+        let a8 = Vector(0, 0);
+        assert_eq!(BitboardIter(KNIGHT_ATTACKS[a8.index()]).collect::<Vec<_>>(), vec![Vector(2, 1), Vector(1, 2)]);
+        assert_eq!(KING_ATTACKS[Vector(4, 4).index()].count_ones(), 8);
+        assert_eq!(BitboardIter(pawn_attacks(Vector(4, 6), Color::White)).collect::<Vec<_>>(), vec![Vector(3, 5), Vector(5, 5)]);
+
+        // Rook on d4 blocked by a piece on d6, which may be captured
+        let occupancy = square_bit(Vector(3, 2));
+        assert_eq!(rook_attacks(Vector(3, 4), occupancy).count_ones(), 7 + 2 + 3);
+    }
+}
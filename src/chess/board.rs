@@ -1,7 +1,7 @@
 use std::{fmt::Display, num::NonZeroU8};
 use itertools::Itertools;
 
-use crate::chess::{r#move::Move, vector::Vector};
+use crate::chess::{bitboard::{square_bit, Bitboard, BitboardIter}, r#move::Move, vector::Vector, zobrist::ZobristTable};
 
 pub const BOARD_SIZE_X: i8 = 8;
 pub const BOARD_SIZE_Y: i8 = 8;
@@ -12,6 +12,7 @@ const PIECE_MOVED_MASK: u8 = 0b0001_0000;
 
 #[repr(u8)]
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[allow(clippy::erasing_op, clippy::identity_op)]
 pub enum Color {
     White = PIECE_COLOR_MASK * 0,
     Black = PIECE_COLOR_MASK * 1,
@@ -51,7 +52,7 @@ impl Color {
 }
 
 #[repr(u8)]
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum PieceType {
     Pawn = 1,
     Knight = 2,
@@ -81,7 +82,7 @@ impl PieceType {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Piece(NonZeroU8);
 
 impl Piece {
@@ -149,17 +150,35 @@ impl Piece {
 }
 
 
+/// Start files of the king and the castling rooks. These only differ from the e, a and h files in Chess960,
+/// where castling still ends with the king and rook on the same squares as in standard chess.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct CastlingFiles {
+    pub king: i8,
+    pub left_rook: i8, // queen side
+    pub right_rook: i8, // king side
+}
 
-type BoardIndex = u8;
+impl Default for CastlingFiles {
+    fn default() -> Self {
+        Self { king: 4, left_rook: 0, right_rook: 7 }
+    }
+}
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Board {
-    pub squares: [[Option<Piece>; 8]; 8], // encoded as Option<Piece<NonZeroU8>> which is u8 with 0 representing None, Rust is cool
+    squares: [[Option<Piece>; 8]; 8], // encoded as Option<Piece<NonZeroU8>> which is u8 with 0 representing None, Rust is cool
+    piece_bitboards: [Bitboard; 6], // by piece type, both colors, kept in sync with squares by set_piece
+    color_bitboards: [Bitboard; 2],
     pub left_castling_rights: [bool; 2],
     pub right_castling_rights: [bool; 2],
+    pub castling_files: CastlingFiles, // the same for both colors
     pub last_move: Option<Move>, // used for detecting e.g. en passant
     pub next_player: Color,
     pub zobrist_hash: u64,
+    pub halfmove_clock: u16, // plies since the last capture or pawn move
+    pub fullmove_number: u16, // starts at 1, incremented after each black move
+    pub hash_history: Vec<u64>, // zobrist hashes of all previous positions, only the last `halfmove_clock` ones can repeat
 }
 
 impl Board {
@@ -169,10 +188,48 @@ impl Board {
         &self.squares[coord.1 as usize][coord.0 as usize]
     }
 
-    pub fn piece_at_mut(&mut self, coord: Vector) -> &mut Option<Piece> {
+    /// Places (or removes) a piece, returning the previous one. This is the only way to change pieces, as it keeps the bitboards in sync.
+    /// The zobrist hash is not updated.
+    pub fn set_piece(&mut self, coord: Vector, piece: Option<Piece>) -> Option<Piece> {
         assert!(coord.is_on_board()); // TODO: only run in debug
 
-        &mut self.squares[coord.1 as usize][coord.0 as usize]
+        let bit = square_bit(coord);
+        let previous = std::mem::replace(&mut self.squares[coord.1 as usize][coord.0 as usize], piece);
+
+        if let Some(previous) = previous {
+            self.piece_bitboards[previous.piece_type().zobrist_index()] &= !bit;
+            self.color_bitboards[previous.color().zobrist_index()] &= !bit;
+        }
+        if let Some(piece) = piece {
+            self.piece_bitboards[piece.piece_type().zobrist_index()] |= bit;
+            self.color_bitboards[piece.color().zobrist_index()] |= bit;
+        }
+
+        previous
+    }
+
+    pub fn take_piece(&mut self, coord: Vector) -> Option<Piece> {
+        self.set_piece(coord, None)
+    }
+
+    #[inline]
+    pub fn occupancy(&self) -> Bitboard {
+        self.color_bitboards[0] | self.color_bitboards[1]
+    }
+
+    #[inline]
+    pub fn color_bitboard(&self, color: Color) -> Bitboard {
+        self.color_bitboards[color.zobrist_index()]
+    }
+
+    #[inline]
+    pub fn piece_type_bitboard(&self, piece_type: PieceType) -> Bitboard {
+        self.piece_bitboards[piece_type.zobrist_index()]
+    }
+
+    #[inline]
+    pub fn piece_bitboard(&self, piece_type: PieceType, color: Color) -> Bitboard {
+        self.piece_type_bitboard(piece_type) & self.color_bitboard(color)
     }
 
     pub fn coords(&self) -> impl Iterator<Item=Vector> {
@@ -180,11 +237,29 @@ impl Board {
     }
 
     pub fn coords_with_piece(&self) -> impl Iterator<Item=Vector> + use<'_> {
-        self.coords().filter(|c| self.piece_at(*c).is_some())
+        BitboardIter(self.occupancy())
     }
 
     pub fn coords_with_piece_of_color(&self, color: Color) -> impl Iterator<Item=Vector> + use<'_> {
-        self.coords().filter(move |c| if let Some(piece) = self.piece_at(*c) { piece.color() == color } else { false } )
+        BitboardIter(self.color_bitboard(color))
+    }
+
+    /// Board without any pieces or castling rights and white to move, pieces can be added with `set_piece`
+    pub fn empty() -> Board {
+        Board {
+            squares: [[None; 8]; 8],
+            piece_bitboards: [0; 6],
+            color_bitboards: [0; 2],
+            left_castling_rights: [false; 2],
+            right_castling_rights: [false; 2],
+            castling_files: CastlingFiles::default(),
+            last_move: None,
+            next_player: Color::White,
+            zobrist_hash: 0,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            hash_history: vec![],
+        }
     }
 }
 
@@ -211,15 +286,20 @@ impl Default for Board {
             pawn_row(White),
             base_row(White),
         ];
-        
-        Self {
-            squares,
+
+        let mut board = Self {
             left_castling_rights: [true; 2],
             right_castling_rights: [true; 2],
-            last_move: None,
-            next_player: White,
-            zobrist_hash: 0,
+            ..Board::empty()
+        };
+
+        for coord in board.coords() {
+            board.set_piece(coord, squares[coord.1 as usize][coord.0 as usize]);
         }
+
+        board.zobrist_hash = board.compute_zobrist_hash(&ZobristTable::default());
+
+        board
     }
 }
 
@@ -0,0 +1,103 @@
+use itertools::Itertools;
+
+use crate::chess::board::{Board, CastlingFiles, Color, Piece, PieceType, BOARD_SIZE_X};
+use crate::chess::vector::Vector;
+use crate::chess::zobrist::ZobristTable;
+
+pub const CHESS960_POSITION_COUNT: u16 = 960;
+/// Index of the standard chess start position
+pub const STANDARD_POSITION_INDEX: u16 = 518;
+
+/// Files of the two knights among the five squares left after placing bishops and queen
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+
+impl Board {
+    /// Chess960 start position by its standard index from 0 to 959, e.g. 518 for the standard chess position
+    pub fn chess960(index: u16) -> Board {
+        assert!(index < CHESS960_POSITION_COUNT, "Chess960 position index {index} out of range");
+
+        let back_rank = chess960_back_rank(index);
+
+        let mut board = Board::empty();
+        board.left_castling_rights = [true; 2];
+        board.right_castling_rights = [true; 2];
+
+        for (x, piece_type) in back_rank.into_iter().enumerate() {
+            let x = x as i8;
+            board.set_piece(Vector(x, 0), Some(Piece::new(Color::Black, piece_type)));
+            board.set_piece(Vector(x, 1), Some(Piece::new(Color::Black, PieceType::Pawn)));
+            board.set_piece(Vector(x, 6), Some(Piece::new(Color::White, PieceType::Pawn)));
+            board.set_piece(Vector(x, 7), Some(Piece::new(Color::White, piece_type)));
+        }
+
+        let file_of = |piece_type| back_rank.iter().position(|t| *t == piece_type).expect("expected piece on back rank") as i8;
+        let rooks = back_rank.iter().positions(|t| *t == PieceType::Rook).map(|x| x as i8).collect_vec();
+        board.castling_files = CastlingFiles { king: file_of(PieceType::King), left_rook: rooks[0], right_rook: rooks[1] };
+
+        board.zobrist_hash = board.compute_zobrist_hash(&ZobristTable::default());
+
+        board
+    }
+}
+
+/// Back rank from a to h following the Scharnagl numbering: the index determines the bishops, queen and knights in turn,
+/// the remaining three squares take rook, king and rook
+fn chess960_back_rank(index: u16) -> [PieceType; 8] {
+    let mut rank = [None; BOARD_SIZE_X as usize];
+    let mut n = index as usize;
+
+    rank[2 * (n % 4) + 1] = Some(PieceType::Bishop); // light squares b, d, f, h
+    n /= 4;
+    rank[2 * (n % 4)] = Some(PieceType::Bishop); // dark squares a, c, e, g
+    n /= 4;
+
+    let mut place_on_empty = |empty_index: usize, piece_type| {
+        let x = (0..rank.len()).filter(|x| rank[*x].is_none()).nth(empty_index).expect("expected enough empty squares");
+        rank[x] = Some(piece_type);
+    };
+
+    place_on_empty(n % 6, PieceType::Queen);
+    n /= 6;
+
+    // The second knight's index shifts by one, as the first knight already took a square
+    let (first_knight, second_knight) = KNIGHT_PLACEMENTS[n];
+    place_on_empty(first_knight, PieceType::Knight);
+    place_on_empty(second_knight - 1, PieceType::Knight);
+
+    for piece_type in [PieceType::Rook, PieceType::King, PieceType::Rook] {
+        place_on_empty(0, piece_type);
+    }
+
+    rank.map(|piece_type| piece_type.expect("expected all squares to be filled"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_positions() {
+        assert_eq!(Board::chess960(STANDARD_POSITION_INDEX), Board::default());
+        assert_eq!(Board::chess960(0).to_fen(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1");
+        assert_eq!(Board::chess960(959).to_shredder_fen(), "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w CAca - 0 1");
+
+        let fens = (0..CHESS960_POSITION_COUNT).map(|index| Board::chess960(index).to_fen());
+        assert_eq!(fens.unique().count(), CHESS960_POSITION_COUNT as usize);
+
+        for back_rank in (0..CHESS960_POSITION_COUNT).map(chess960_back_rank) {
+            let bishops = back_rank.iter().positions(|t| *t == PieceType::Bishop).collect_vec();
+            let rooks_and_king = back_rank.iter().filter(|t| matches!(t, PieceType::Rook | PieceType::King)).collect_vec();
+            assert_ne!(bishops[0] % 2, bishops[1] % 2);
+            assert_eq!(rooks_and_king, [&PieceType::Rook, &PieceType::King, &PieceType::Rook]);
+        }
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        for index in [0, 1, 100, 959] {
+            let board = Board::chess960(index);
+            assert_eq!(Board::from_fen(&board.to_fen()).unwrap(), board);
+            assert_eq!(Board::from_fen(&board.to_shredder_fen()).unwrap(), board);
+        }
+    }
+}
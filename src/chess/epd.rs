@@ -0,0 +1,194 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+
+use crate::chess::board::Board;
+use crate::chess::fen::FenError;
+use crate::chess::r#move::Move;
+use crate::chess::san::SanError;
+use crate::chess::zobrist::ZobristTable;
+
+/// Number of leading whitespace separated fields describing the position, like FEN without the move counters
+const POSITION_FIELDS: usize = 4;
+
+/// A position of an Extended Position Description file, as used by test suites like WAC or STS
+#[derive(Clone, Debug)]
+pub struct EpdRecord {
+    pub board: Board,
+    pub id: Option<String>,
+    pub comment: Option<String>, // c0
+    pub best_moves: Vec<Move>, // bm
+    pub avoid_moves: Vec<Move>, // am
+    pub operations: Vec<(String, Vec<String>)>, // all operations in record order, including the ones above
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum EpdError {
+    InvalidFen(FenError),
+    UnterminatedString,
+    InvalidOperand { opcode: String, operand: String },
+    InvalidMove { opcode: String, error: SanError },
+}
+
+impl Display for EpdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EpdError::InvalidFen(error) => write!(f, "invalid position: {error}"),
+            EpdError::UnterminatedString => write!(f, "string operand is missing its closing '\"'"),
+            EpdError::InvalidOperand { opcode, operand } => write!(f, "invalid operand '{operand}' for opcode '{opcode}'"),
+            EpdError::InvalidMove { opcode, error } => write!(f, "invalid move for opcode '{opcode}': {error}"),
+        }
+    }
+}
+
+impl std::error::Error for EpdError {}
+
+impl From<FenError> for EpdError {
+    fn from(error: FenError) -> Self {
+        EpdError::InvalidFen(error)
+    }
+}
+
+impl EpdRecord {
+    /// Parses a single EPD line like `<fen fields> bm Qg6; id "WAC.001";`.
+    /// The `hmvc` and `fmvn` opcodes set the move counters, the moves of `bm` and `am` are given in SAN.
+    pub fn from_epd(epd: &str, zobrist_table: &ZobristTable) -> Result<EpdRecord, EpdError> {
+        let position_fields = epd.split_whitespace().take(POSITION_FIELDS).join(" ");
+        let mut board = Board::from_fen(&position_fields)?;
+
+        let mut operations_text = epd.trim_start();
+        for _ in 0..POSITION_FIELDS {
+            operations_text = operations_text.trim_start_matches(|c: char| !c.is_whitespace()).trim_start();
+        }
+        let operations = parse_operations(operations_text)?;
+
+        let mut id = None;
+        let mut comment = None;
+        for (opcode, operands) in &operations {
+            let invalid_operand = || EpdError::InvalidOperand { opcode: opcode.clone(), operand: operands.join(" ") };
+
+            match opcode.as_str() {
+                "id" => id = operands.first().cloned(),
+                "c0" => comment = operands.first().cloned(),
+                "hmvc" => board.halfmove_clock = operands.first().and_then(|operand| operand.parse().ok()).ok_or_else(invalid_operand)?,
+                "fmvn" => {
+                    board.fullmove_number = operands.first().and_then(|operand| operand.parse().ok())
+                        .filter(|n| *n > 0)
+                        .ok_or_else(invalid_operand)?
+                }
+                _ => {}
+            }
+        }
+
+        board.zobrist_hash = board.compute_zobrist_hash(zobrist_table);
+
+        let mut best_moves = vec![];
+        let mut avoid_moves = vec![];
+        for (opcode, operands) in &operations {
+            let moves = match opcode.as_str() {
+                "bm" => &mut best_moves,
+                "am" => &mut avoid_moves,
+                _ => continue,
+            };
+
+            for san in operands {
+                let r#move = board.parse_san(san).map_err(|error| EpdError::InvalidMove { opcode: opcode.clone(), error })?;
+                moves.push(r#move);
+            }
+        }
+
+        Ok(EpdRecord { board, id, comment, best_moves, avoid_moves, operations })
+    }
+
+    pub fn operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations.iter().find(|(name, _)| name == opcode).map(|(_, operands)| operands.as_slice())
+    }
+
+    /// Whether a move found by the engine solves the position: it has to be one of the best moves if any are given,
+    /// and must not be one of the moves to avoid
+    pub fn is_solved_by(&self, r#move: Move) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.contains(&r#move)) && !self.avoid_moves.contains(&r#move)
+    }
+}
+
+/// Parses all records of an EPD file, skipping empty lines. Errors are reported with their 1-based line number.
+pub fn parse_epd(text: &str, zobrist_table: &ZobristTable) -> Result<Vec<EpdRecord>, (usize, EpdError)> {
+    text.lines().enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| EpdRecord::from_epd(line, zobrist_table).map_err(|error| (index + 1, error)))
+        .collect()
+}
+
+/// Splits operations like `bm Nf3 Ne5; id "a; b";` into opcodes and operands, the last semicolon may be missing
+fn parse_operations(text: &str) -> Result<Vec<(String, Vec<String>)>, EpdError> {
+    let mut operations = vec![];
+    let mut tokens: Vec<String> = vec![];
+    let mut chars = text.chars().peekable();
+
+    loop {
+        match chars.next() {
+            None | Some(';') => {
+                if let Some((opcode, operands)) = tokens.split_first() {
+                    operations.push((opcode.clone(), operands.to_vec()));
+                }
+                tokens.clear();
+
+                if chars.peek().is_none() {
+                    break;
+                }
+            }
+            Some('"') => {
+                let string = chars.by_ref().take_while_inclusive(|c| *c != '"').collect::<String>();
+                tokens.push(string.strip_suffix('"').ok_or(EpdError::UnterminatedString)?.to_owned());
+            }
+            Some(c) if c.is_whitespace() => {}
+            Some(c) => {
+                let mut token = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ';') {
+                    token.push(c);
+                }
+                tokens.push(token);
+            }
+        }
+    }
+
+    Ok(operations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_record() {
+        let zobrist_table = ZobristTable::default();
+        let record = EpdRecord::from_epd(r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001"; c0 "mate; in 3";"#, &zobrist_table).unwrap();
+
+        assert_eq!(record.board.to_fen(), "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1");
+        assert_eq!(record.id.as_deref(), Some("WAC.001"));
+        assert_eq!(record.comment.as_deref(), Some("mate; in 3"));
+        assert_eq!(record.best_moves.iter().map(|r#move| r#move.to_uci()).collect::<Vec<_>>(), vec!["g3g6"]);
+        assert_eq!(record.operation("bm"), Some(["Qg6".to_owned()].as_slice()));
+        assert!(record.is_solved_by(record.board.parse_uci_move("g3g6").unwrap()));
+        assert!(!record.is_solved_by(record.board.parse_uci_move("g3g4").unwrap()));
+
+        let multiple = EpdRecord::from_epd("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - am Nxe5 Ng5; hmvc 2; fmvn 3", &zobrist_table).unwrap();
+        assert_eq!(multiple.avoid_moves.len(), 2);
+        assert!(multiple.best_moves.is_empty());
+        assert_eq!(multiple.board.to_fen(), "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        assert_eq!(multiple.board.zobrist_hash, multiple.board.compute_zobrist_hash(&zobrist_table));
+    }
+
+    #[test]
+    fn errors() {
+        let zobrist_table = ZobristTable::default();
+
+        assert!(matches!(EpdRecord::from_epd("8/8/8 w - - bm e4;", &zobrist_table), Err(EpdError::InvalidFen(_))));
+        assert_eq!(EpdRecord::from_epd("4k3/8/8/8/8/8/8/4K3 w - - id \"open;", &zobrist_table).unwrap_err(), EpdError::UnterminatedString);
+        assert!(matches!(EpdRecord::from_epd("4k3/8/8/8/8/8/8/4K3 w - - bm e4;", &zobrist_table), Err(EpdError::InvalidMove { .. })));
+        assert_eq!(
+            parse_epd("4k3/8/8/8/8/8/8/4K3 w - - id \"ok\";\n\n4k3/8/8/8/8/8/8/4K3 w - - hmvc x;", &zobrist_table).unwrap_err(),
+            (3, EpdError::InvalidOperand { opcode: "hmvc".to_owned(), operand: "x".to_owned() }),
+        );
+    }
+}
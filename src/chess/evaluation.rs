@@ -1,14 +1,19 @@
+use crate::chess::bitboard::Bitboard;
 use crate::chess::board::{Board, Piece, PieceType};
 use crate::chess::board::Color::{Black, White};
 use crate::chess::r#move::Move;
-use crate::chess::vector::Vector;
 
 const MATERIAL_WEIGHT: f64 = 1.0;
 const MOBILITY_WEIGHT: f64 = 0.1;
 const CENTER_WEIGHT: f64 = 0.6;
 const CENTER_PUSHED_WEIGHT: f64 = 0.2;
+#[allow(dead_code)] // capture potential isn't part of the evaluation yet
 const CAPTURE_POTENTIAL_WEIGHT: f64 = 0.25;
 
+const CENTER_SQUARES: Bitboard = (1 << 27) | (1 << 28) | (1 << 35) | (1 << 36); // d5, e5, d4, e4
+const WHITE_CENTER_PAWN_SQUARES: Bitboard = (1 << 51) | (1 << 52); // d2, e2
+const BLACK_CENTER_PAWN_SQUARES: Bitboard = (1 << 11) | (1 << 12); // d7, e7
+
 impl Board {
     pub fn evaluate_position_for_current_player(&self) -> f64 {
         let evaluation = self.evaluate_position();
@@ -16,11 +21,15 @@ impl Board {
         if self.next_player == White {
             evaluation
         } else {
-            evaluation * -1.0
+            -evaluation
         }
     }
 
     pub fn evaluate_position(&self) -> f64 {
+        if self.is_insufficient_material() {
+            return 0.0;
+        }
+
         let mut evaluation = 0.0;
 
         let white_moves = self.generate_moves(White); // TODO: could be used to detect check
@@ -36,70 +45,45 @@ impl Board {
         evaluation
     }
 
-    fn evaluate_material(&self) -> f64 { // TODO: faster?
-        self.squares.iter()
-            .flat_map(|row|
-                row.iter().map(|s| {
-                    if let Some(p) = s {
-                        p.piece_value()
-                    } else {
-                        0.0
-                    }
-                })
-            ).sum::<f64>() * MATERIAL_WEIGHT
+    fn evaluate_material(&self) -> f64 {
+        [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen].into_iter()
+            .map(|piece_type| {
+                let white_count = self.piece_bitboard(piece_type, White).count_ones();
+                let black_count = self.piece_bitboard(piece_type, Black).count_ones();
+                (white_count as f64 - black_count as f64) * piece_type.base_value()
+            })
+            .sum::<f64>() * MATERIAL_WEIGHT
     }
 
-    fn evaluate_mobility(&self, white_moves: &Vec<Move>, black_moves: &Vec<Move>) -> f64 {
+    fn evaluate_mobility(&self, white_moves: &[Move], black_moves: &[Move]) -> f64 {
         (white_moves.len() as f64 - black_moves.len() as f64) * MOBILITY_WEIGHT
     }
 
     fn evaluate_center(&self) -> f64 {
-        let mut evaluation = 0.0;
-
-        for y in 3..=4 {
-            for x in 3..=4 {
-                if let Some(piece) = self.piece_at(Vector(x, y)) {
-                    if piece.is_pawn() {
-                        evaluation += CENTER_WEIGHT * piece.color().value_multiplier();
-                    }
-                }
-            }
-        }
+        let white_pawns = (self.piece_bitboard(PieceType::Pawn, White) & CENTER_SQUARES).count_ones();
+        let black_pawns = (self.piece_bitboard(PieceType::Pawn, Black) & CENTER_SQUARES).count_ones();
 
-        evaluation
+        (white_pawns as f64 - black_pawns as f64) * CENTER_WEIGHT
     }
 
     fn evaluate_center_pushed(&self) -> f64 {
-        let mut evaluation = 0.0;
+        let white_unpushed = (self.piece_bitboard(PieceType::Pawn, White) & WHITE_CENTER_PAWN_SQUARES).count_ones();
+        let black_unpushed = (self.piece_bitboard(PieceType::Pawn, Black) & BLACK_CENTER_PAWN_SQUARES).count_ones();
 
-        for x in 3..=4 {
-            if let Some(piece) = self.piece_at(Vector(x, 6)) {
-                if piece.is_pawn() && piece.is_white() {
-                    evaluation -= CENTER_PUSHED_WEIGHT;
-                }
-            }
-        }
-        for x in 3..=4 {
-            if let Some(piece) = self.piece_at(Vector(x, 1)) {
-                if piece.is_pawn() && piece.is_black() {
-                    evaluation += CENTER_PUSHED_WEIGHT;
-                }
-            }
-        }
-
-        evaluation
+        (black_unpushed as f64 - white_unpushed as f64) * CENTER_PUSHED_WEIGHT
     }
 
 
-    fn evaluate_capture_potential(&self, white_moves: &Vec<Move>, black_moves: &Vec<Move>) -> f64 {
+    #[allow(dead_code)]
+    fn evaluate_capture_potential(&self, white_moves: &[Move], black_moves: &[Move]) -> f64 {
         let white_capture_potential: f64 = white_moves.iter()
             .filter(|r#move| r#move.is_capture_with_target() && !r#move.is_capture_king(self))
-            .map(|r#move| self.piece_at(r#move.dst).unwrap().piece_value())
+            .map(|r#move| self.piece_at(r#move.dst()).unwrap().piece_value())
             .sum();
 
         let black_capture_potential: f64 = black_moves.iter()
             .filter(|r#move| r#move.is_capture_with_target() && !r#move.is_capture_king(self))
-            .map(|r#move| self.piece_at(r#move.dst).unwrap().piece_value())
+            .map(|r#move| self.piece_at(r#move.dst()).unwrap().piece_value())
             .sum();
 
         (white_capture_potential - black_capture_potential) * CAPTURE_POTENTIAL_WEIGHT
@@ -121,24 +105,28 @@ impl Board {
     // }
 
     // fn evaluate_check(&self, white_moves: &Vec<Move>, black_moves: &Vec<Move>) -> f64 { // could check if king -> return 200, but: very expensive
-    //     if white_moves.iter().any(|m| self.piece_at(m.dst)) // check move kind for capture instead?
+    //     if white_moves.iter().any(|m| self.piece_at(m.dst())) // check move kind for capture instead?
     // }
 
     // TODO: doubled, isolated, blocked pawns
 }
 
-impl Piece {
+impl PieceType {
     // Piece value based on AlphaZero
-    pub fn piece_value(&self) -> f64 {
-        let base_value = match self.piece_type() {
+    pub fn base_value(self) -> f64 {
+        match self {
             PieceType::Pawn => 1.0,
             PieceType::Knight => 3.05,
             PieceType::Bishop => 3.33,
             PieceType::Rook => 5.63,
             PieceType::Queen => 9.5,
             PieceType::King => 200.0,
-        };
+        }
+    }
+}
 
-        base_value * if self.is_white() { 1.0 } else { -1.0 }
+impl Piece {
+    pub fn piece_value(&self) -> f64 {
+        self.piece_type().base_value() * if self.is_white() { 1.0 } else { -1.0 }
     }
 }
\ No newline at end of file
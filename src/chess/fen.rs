@@ -0,0 +1,370 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+
+use crate::chess::board::{Board, CastlingFiles, Color, Piece, PieceType, BOARD_SIZE_X, BOARD_SIZE_Y};
+use crate::chess::r#move::{Move, MoveKind};
+use crate::chess::vector::Vector;
+use crate::chess::zobrist::ZobristTable;
+
+pub const START_POSITION_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FenError {
+    MissingPiecePlacement,
+    MissingSideToMove,
+    MissingCastlingRights,
+    MissingEnPassantSquare,
+    TooManyFields(usize),
+    WrongRankCount(usize),
+    WrongRankLength { rank: String, length: usize },
+    InvalidPiece(char),
+    PawnOnBackRank(Vector),
+    InvalidSideToMove(String),
+    InvalidCastlingRights(String),
+    InvalidEnPassantSquare(String),
+    ImpossibleEnPassant(Vector),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::MissingPiecePlacement => write!(f, "missing piece placement field"),
+            FenError::MissingSideToMove => write!(f, "missing side to move field"),
+            FenError::MissingCastlingRights => write!(f, "missing castling rights field"),
+            FenError::MissingEnPassantSquare => write!(f, "missing en passant square field"),
+            FenError::TooManyFields(count) => write!(f, "expected at most 6 fields, found {count}"),
+            FenError::WrongRankCount(count) => write!(f, "expected 8 ranks in piece placement, found {count}"),
+            FenError::WrongRankLength { rank, length } => write!(f, "rank '{rank}' describes {length} squares instead of 8"),
+            FenError::InvalidPiece(c) => write!(f, "invalid piece '{c}' in piece placement"),
+            FenError::PawnOnBackRank(coord) => write!(f, "pawn on {coord}, pawns can't stand on the first or last rank"),
+            FenError::InvalidSideToMove(s) => write!(f, "invalid side to move '{s}', expected 'w' or 'b'"),
+            FenError::InvalidCastlingRights(s) => write!(f, "invalid castling rights '{s}', expected '-', a subset of 'KQkq' or rook files like 'HAha'"),
+            FenError::InvalidEnPassantSquare(s) => write!(f, "invalid en passant square '{s}'"),
+            FenError::ImpossibleEnPassant(square) => write!(f, "en passant square {square} doesn't follow a double pawn push"),
+            FenError::InvalidHalfmoveClock(s) => write!(f, "invalid halfmove clock '{s}'"),
+            FenError::InvalidFullmoveNumber(s) => write!(f, "invalid fullmove number '{s}'"),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl Board {
+    /// Parses a position in Forsyth-Edwards Notation. The move counters may be omitted, as in EPD, and default to "0 1".
+    /// Castling rights may name the rook files as in Shredder-FEN and X-FEN, which is needed for some Chess960 positions.
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let fields = fen.split_whitespace().collect_vec();
+        if fields.len() > 6 {
+            return Err(FenError::TooManyFields(fields.len()));
+        }
+
+        let piece_placement = *fields.first().ok_or(FenError::MissingPiecePlacement)?;
+        let side_to_move = *fields.get(1).ok_or(FenError::MissingSideToMove)?;
+        let castling_rights = *fields.get(2).ok_or(FenError::MissingCastlingRights)?;
+        let en_passant_square = *fields.get(3).ok_or(FenError::MissingEnPassantSquare)?;
+
+        let squares = parse_piece_placement(piece_placement)?;
+
+        let mut board = Board::empty();
+        for coord in board.coords() {
+            board.set_piece(coord, squares[coord.1 as usize][coord.0 as usize]);
+        }
+
+        let back_rank_pawn = board.coords_with_piece()
+            .find(|coord| (coord.1 == 0 || coord.1 == BOARD_SIZE_Y - 1) && board.piece_at(*coord).is_some_and(|piece| piece.is_pawn()));
+        if let Some(coord) = back_rank_pawn {
+            return Err(FenError::PawnOnBackRank(coord));
+        }
+
+        board.next_player = match side_to_move {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::InvalidSideToMove(side_to_move.to_owned())),
+        };
+
+        if castling_rights != "-" {
+            let invalid = || FenError::InvalidCastlingRights(castling_rights.to_owned());
+
+            // Files are shared by both colors, rights referring to different ones are rejected
+            let mut king_file = None;
+            let mut rook_files = [None; 2]; // queen side, king side
+            for c in castling_rights.chars() {
+                let (color, king_side, king, rook) = parse_castling_right(&board, c).ok_or_else(invalid)?;
+
+                let rights = if king_side { &mut board.right_castling_rights } else { &mut board.left_castling_rights };
+                if rights[color.zobrist_index()] {
+                    return Err(invalid()); // duplicate
+                }
+                rights[color.zobrist_index()] = true;
+
+                let king_conflicts = king_file.replace(king).is_some_and(|previous| previous != king);
+                let rook_conflicts = rook_files[king_side as usize].replace(rook).is_some_and(|previous| previous != rook);
+                if king_conflicts || rook_conflicts {
+                    return Err(invalid());
+                }
+            }
+
+            let standard = CastlingFiles::default();
+            board.castling_files = CastlingFiles {
+                king: king_file.unwrap_or(standard.king),
+                left_rook: rook_files[0].unwrap_or(standard.left_rook),
+                right_rook: rook_files[1].unwrap_or(standard.right_rook),
+            };
+        }
+
+        // En passant is derived from the last move, so the double pawn push leading to the square is reconstructed
+        if en_passant_square != "-" {
+            let invalid = || FenError::InvalidEnPassantSquare(en_passant_square.to_owned());
+            let square: Vector = en_passant_square.parse().map_err(|_| invalid())?;
+
+            let last_move = match (board.next_player, square.1) {
+                (Color::White, 2) => Move::new(Vector(square.0, 1), Vector(square.0, 3), MoveKind::DoublePawnPush),
+                (Color::Black, 5) => Move::new(Vector(square.0, 6), Vector(square.0, 4), MoveKind::DoublePawnPush),
+                _ => return Err(invalid()),
+            };
+
+            // The pawn must stand where it was pushed to, with the squares it passed empty
+            let pushed_pawn = Piece::new(board.next_player.other(), PieceType::Pawn);
+            let is_double_push = *board.piece_at(last_move.dst()) == Some(pushed_pawn)
+                && board.piece_at(square).is_none()
+                && board.piece_at(last_move.src()).is_none();
+            if !is_double_push {
+                return Err(FenError::ImpossibleEnPassant(square));
+            }
+            board.last_move = Some(last_move);
+        }
+
+        if let Some(halfmove_clock) = fields.get(4) {
+            board.halfmove_clock = halfmove_clock.parse().map_err(|_| FenError::InvalidHalfmoveClock(halfmove_clock.to_string()))?;
+        }
+
+        if let Some(fullmove_number) = fields.get(5) {
+            board.fullmove_number = fullmove_number.parse().ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| FenError::InvalidFullmoveNumber(fullmove_number.to_string()))?;
+        }
+
+        board.zobrist_hash = board.compute_zobrist_hash(&ZobristTable::default());
+
+        Ok(board)
+    }
+
+    /// Castling rights are written as in X-FEN: "KQkq" unless another rook is closer to the corner than the castling rook,
+    /// which is then named by its file. This is plain FEN for all standard chess positions.
+    pub fn to_fen(&self) -> String {
+        self.fen_with_castling_field(false)
+    }
+
+    /// Like `to_fen`, but with castling rights always given as rook files as in Shredder-FEN, e.g. "HAha"
+    pub fn to_shredder_fen(&self) -> String {
+        self.fen_with_castling_field(true)
+    }
+
+    fn fen_with_castling_field(&self, shredder: bool) -> String {
+        let piece_placement = (0..BOARD_SIZE_Y).map(|y| {
+            let mut rank = String::new();
+            let mut empty_squares = 0;
+
+            for x in 0..BOARD_SIZE_X {
+                if let Some(piece) = self.piece_at(Vector(x, y)) {
+                    if empty_squares > 0 {
+                        rank.push_str(&empty_squares.to_string());
+                        empty_squares = 0;
+                    }
+                    rank.push(piece_to_fen_char(piece));
+                } else {
+                    empty_squares += 1;
+                }
+            }
+
+            if empty_squares > 0 {
+                rank.push_str(&empty_squares.to_string());
+            }
+
+            rank
+        }).join("/");
+
+        let side_to_move = match self.next_player {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        let mut castling_rights = String::new();
+        for color in [Color::White, Color::Black] {
+            let y = if color == Color::White { BOARD_SIZE_Y - 1 } else { 0 };
+            let is_own_rook = |x: i8| self.piece_at(Vector(x, y)).is_some_and(|piece| piece.is_rook() && piece.color() == color);
+
+            for (rights, rook_file, side_char, corner_files) in [
+                (self.right_castling_rights, self.castling_files.right_rook, 'K', self.castling_files.right_rook + 1..BOARD_SIZE_X),
+                (self.left_castling_rights, self.castling_files.left_rook, 'Q', 0..self.castling_files.left_rook),
+            ] {
+                if !rights[color.zobrist_index()] {
+                    continue;
+                }
+
+                let c = if shredder || corner_files.into_iter().any(is_own_rook) { Vector(rook_file, y).file_char() } else { side_char.to_ascii_lowercase() };
+                castling_rights.push(if color == Color::White { c.to_ascii_uppercase() } else { c });
+            }
+        }
+        if castling_rights.is_empty() {
+            castling_rights.push('-');
+        }
+
+        let en_passant_square = match self.last_move {
+            Some(last_move) if last_move.kind() == MoveKind::DoublePawnPush => {
+                Vector(last_move.src().0, (last_move.src().1 + last_move.dst().1) / 2).to_string()
+            }
+            _ => "-".to_owned(),
+        };
+
+        format!("{piece_placement} {side_to_move} {castling_rights} {en_passant_square} {} {}", self.halfmove_clock, self.fullmove_number)
+    }
+}
+
+fn parse_piece_placement(piece_placement: &str) -> Result<[[Option<Piece>; 8]; 8], FenError> {
+    let mut squares = [[None; 8]; 8];
+
+    let ranks = piece_placement.split('/').collect_vec();
+    if ranks.len() != BOARD_SIZE_Y as usize {
+        return Err(FenError::WrongRankCount(ranks.len()));
+    }
+
+    // FEN starts with the 8th rank, which is row 0
+    for (y, rank) in ranks.iter().enumerate() {
+        let mut x = 0;
+        for c in rank.chars() {
+            if let Some(empty_squares) = c.to_digit(10) {
+                x += empty_squares as usize;
+            } else {
+                let piece = piece_from_fen_char(c).ok_or(FenError::InvalidPiece(c))?;
+                if x < BOARD_SIZE_X as usize {
+                    squares[y][x] = Some(piece);
+                }
+                x += 1;
+            }
+        }
+
+        if x != BOARD_SIZE_X as usize {
+            return Err(FenError::WrongRankLength { rank: rank.to_string(), length: x });
+        }
+    }
+
+    Ok(squares)
+}
+
+/// Color, side (true for the king side) and the files of the king and rook a castling right refers to.
+/// "KQkq" refer to the outermost rook on that side of the king, Shredder-FEN and X-FEN may name the rook's file instead.
+fn parse_castling_right(board: &Board, c: char) -> Option<(Color, bool, i8, i8)> {
+    let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+    let y = if color == Color::White { BOARD_SIZE_Y - 1 } else { 0 };
+    let is_own = |x: &i8, piece_type| board.piece_at(Vector(*x, y)).is_some_and(|piece| piece.piece_type() == piece_type && piece.color() == color);
+    let standard = CastlingFiles::default();
+
+    // Without the king on its back rank the right can't be used anyway, the standard files keep it representable
+    let Some(king) = (0..BOARD_SIZE_X).find(|x| is_own(x, PieceType::King)) else {
+        return match c.to_ascii_lowercase() {
+            'k' => Some((color, true, standard.king, standard.right_rook)),
+            'q' => Some((color, false, standard.king, standard.left_rook)),
+            _ => None,
+        };
+    };
+
+    match c.to_ascii_lowercase() {
+        'k' => Some((color, true, king, (king + 1..BOARD_SIZE_X).rev().find(|x| is_own(x, PieceType::Rook)).unwrap_or(standard.right_rook))),
+        'q' => Some((color, false, king, (0..king).find(|x| is_own(x, PieceType::Rook)).unwrap_or(standard.left_rook))),
+        file @ 'a'..='h' => {
+            let rook = file as i8 - 'a' as i8;
+            (rook != king).then_some((color, rook > king, king, rook))
+        }
+        _ => None,
+    }
+}
+
+fn piece_from_fen_char(c: char) -> Option<Piece> {
+    let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+    let piece_type = match c.to_ascii_lowercase() {
+        'p' => PieceType::Pawn,
+        'n' => PieceType::Knight,
+        'b' => PieceType::Bishop,
+        'r' => PieceType::Rook,
+        'q' => PieceType::Queen,
+        'k' => PieceType::King,
+        _ => return None,
+    };
+
+    Some(Piece::new(color, piece_type))
+}
+
+fn piece_to_fen_char(piece: &Piece) -> char {
+    let c = match piece.piece_type() {
+        PieceType::Pawn => 'p',
+        PieceType::Knight => 'n',
+        PieceType::Bishop => 'b',
+        PieceType::Rook => 'r',
+        PieceType::Queen => 'q',
+        PieceType::King => 'k',
+    };
+
+    if piece.is_white() { c.to_ascii_uppercase() } else { c }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_position() {
+        let board = Board::from_fen(START_POSITION_FEN).unwrap();
+        assert_eq!(board, Board::default());
+        assert_eq!(Board::default().to_fen(), START_POSITION_FEN);
+    }
+
+    #[test]
+    fn round_trip() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 17 42",
+        ] {
+            assert_eq!(Board::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn en_passant_square_enables_capture() {
+        let board = Board::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3").unwrap();
+        let ep_captures = board.generate_legal_moves(Color::White).into_iter().filter(|m| m.kind() == MoveKind::EPCapture).collect_vec();
+        assert_eq!(ep_captures, vec![Move::new(Vector(4, 3), Vector(5, 2), MoveKind::EPCapture)]);
+    }
+
+    #[test]
+    fn missing_move_counters_default() {
+        let board = Board::from_fen("8/8/8/8/8/8/8/K6k w - -").unwrap();
+        assert_eq!(board.halfmove_clock, 0);
+        assert_eq!(board.fullmove_number, 1);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Board::from_fen(""), Err(FenError::MissingPiecePlacement));
+        assert_eq!(Board::from_fen("8/8/8/8/8/8/8/K6k"), Err(FenError::MissingSideToMove));
+        assert_eq!(Board::from_fen("8/8/8/8/8/8/K6k w - - 0 1"), Err(FenError::WrongRankCount(7)));
+        assert_eq!(Board::from_fen("8/8/8/8/8/8/8/K5k w - - 0 1"), Err(FenError::WrongRankLength { rank: "K5k".to_owned(), length: 7 }));
+        assert_eq!(Board::from_fen("8/8/8/8/8/8/8/K6x w - - 0 1"), Err(FenError::InvalidPiece('x')));
+        assert_eq!(Board::from_fen("P7/8/8/8/8/8/8/K6k w - - 0 1"), Err(FenError::PawnOnBackRank(Vector(0, 0))));
+        assert_eq!(Board::from_fen("8/8/8/8/8/8/8/K5pk b - - 0 1"), Err(FenError::PawnOnBackRank(Vector(6, 7))));
+        assert_eq!(Board::from_fen("8/8/8/8/8/8/8/K6k x - - 0 1"), Err(FenError::InvalidSideToMove("x".to_owned())));
+        assert_eq!(Board::from_fen("8/8/8/8/8/8/8/K6k w KK - 0 1"), Err(FenError::InvalidCastlingRights("KK".to_owned())));
+        assert_eq!(Board::from_fen("8/8/8/8/8/8/8/K6k w - e4 0 1"), Err(FenError::InvalidEnPassantSquare("e4".to_owned())));
+        assert_eq!(Board::from_fen("8/8/8/3P4/8/8/8/K6k w - e6 0 1"), Err(FenError::ImpossibleEnPassant(Vector(4, 2))));
+        assert_eq!(Board::from_fen("8/4p3/8/3Pp3/8/8/8/K6k w - e6 0 1"), Err(FenError::ImpossibleEnPassant(Vector(4, 2))));
+        assert_eq!(Board::from_fen("8/8/8/8/3pP3/8/8/K6k w - e3 0 1"), Err(FenError::InvalidEnPassantSquare("e3".to_owned())));
+        assert_eq!(Board::from_fen("8/8/8/8/8/8/8/K6k w - - x 1"), Err(FenError::InvalidHalfmoveClock("x".to_owned())));
+        assert_eq!(Board::from_fen("8/8/8/8/8/8/8/K6k w - - 0 0"), Err(FenError::InvalidFullmoveNumber("0".to_owned())));
+    }
+}
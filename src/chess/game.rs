@@ -0,0 +1,202 @@
+use crate::chess::board::Board;
+use crate::chess::game_status::{GameResult, GameStatus};
+use crate::chess::r#move::Move;
+use crate::chess::validation::IllegalMove;
+use crate::chess::zobrist::ZobristTable;
+
+/// A move of a game together with the zobrist hash of the position it led to
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GameMove {
+    pub r#move: Move,
+    pub zobrist_hash: u64,
+    pub annotation: Annotation,
+}
+
+/// Commentary following a move, as read from and written to PGN
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Annotation {
+    pub comments: Vec<String>,
+    pub nags: Vec<u8>, // numeric annotation glyphs, e.g. 1 for "!" and 4 for "??"
+    pub variations: Vec<Variation>, // alternatives to the move, played from the position before it
+}
+
+/// An alternative line of moves, which can have annotations and variations of its own
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Variation {
+    pub comments: Vec<String>, // before the first move
+    pub moves: Vec<GameMove>,
+}
+
+/// Record of a game from its start position, which can be navigated to any ply.
+/// Shared by the UI, PGN import and export and protocol front ends.
+#[derive(Clone, Debug)]
+pub struct Game {
+    pub tags: Vec<(String, String)>, // metadata like players and event, in PGN tag order
+    pub result: GameResult,
+    pub comments: Vec<String>, // before the first move
+    start_position: Board,
+    moves: Vec<GameMove>,
+    ply: usize, // number of moves played to reach the current position
+    board: Board, // position after `ply` moves
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new(Board::default())
+    }
+}
+
+impl Game {
+    pub fn new(start_position: Board) -> Self {
+        Self {
+            tags: vec![],
+            result: GameResult::Unknown,
+            comments: vec![],
+            board: start_position.clone(),
+            start_position,
+            moves: vec![],
+            ply: 0,
+        }
+    }
+
+    pub fn start_position(&self) -> &Board {
+        &self.start_position
+    }
+
+    /// The position at the current ply
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn moves(&self) -> &[GameMove] {
+        &self.moves
+    }
+
+    /// Annotation of the move that led to the current position, none at the start of the game
+    pub fn annotation_mut(&mut self) -> Option<&mut Annotation> {
+        let ply = self.ply.checked_sub(1)?;
+        self.moves.get_mut(ply).map(|game_move| &mut game_move.annotation)
+    }
+
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    pub fn status(&self) -> GameStatus {
+        self.board.game_status()
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag_name, _)| tag_name == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag_name, _)| tag_name == name) {
+            Some((_, existing)) => *existing = value.to_owned(),
+            None => self.tags.push((name.to_owned(), value.to_owned())),
+        }
+    }
+
+    /// Plays the move in the current position. Moves after the current ply are discarded, as the game continues differently.
+    pub fn push_move(&mut self, r#move: Move, zobrist_table: &ZobristTable) -> Result<(), IllegalMove> {
+        self.board.try_execute_move(r#move, zobrist_table)?;
+
+        self.moves.truncate(self.ply);
+        self.moves.push(GameMove { r#move, zobrist_hash: self.board.zobrist_hash, annotation: Annotation::default() });
+        self.ply += 1;
+        self.result = self.status().into();
+
+        Ok(())
+    }
+
+    /// Replays the moves from the start position up to the given ply, clamped to the number of moves
+    pub fn go_to_ply(&mut self, ply: usize, zobrist_table: &ZobristTable) {
+        self.ply = ply.min(self.moves.len());
+        self.board = self.start_position.clone();
+
+        for game_move in &self.moves[..self.ply] {
+            self.board.execute_move(game_move.r#move, zobrist_table);
+            debug_assert_eq!(self.board.zobrist_hash, game_move.zobrist_hash);
+        }
+    }
+
+    /// Goes back one ply, returns false at the start of the game
+    pub fn back(&mut self, zobrist_table: &ZobristTable) -> bool {
+        if self.ply == 0 {
+            return false;
+        }
+
+        self.go_to_ply(self.ply - 1, zobrist_table);
+        true
+    }
+
+    /// Goes forward one ply, returns false at the end of the game
+    pub fn forward(&mut self, zobrist_table: &ZobristTable) -> bool {
+        let Some(game_move) = self.moves.get(self.ply) else {
+            return false;
+        };
+
+        self.board.execute_move(game_move.r#move, zobrist_table);
+        self.ply += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::r#move::MoveKind;
+    use crate::chess::vector::*;
+
+    #[test]
+    fn navigation_replays_moves() {
+        let zobrist_table = ZobristTable::default();
+        let mut game = Game::default();
+
+        for uci in ["e2e4", "e7e5", "g1f3", "b8c6"] {
+            let r#move = game.board().parse_uci_move(uci).unwrap();
+            game.push_move(r#move, &zobrist_table).unwrap();
+        }
+        let final_position = game.board().clone();
+        assert_eq!(game.moves().last().unwrap().zobrist_hash, final_position.zobrist_hash);
+
+        game.go_to_ply(1, &zobrist_table);
+        assert_eq!(game.board().to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+
+        assert!(game.back(&zobrist_table));
+        assert!(!game.back(&zobrist_table));
+        assert_eq!(game.board(), &Board::default());
+
+        game.go_to_ply(10, &zobrist_table);
+        assert_eq!(game.ply(), 4);
+        assert!(!game.forward(&zobrist_table));
+        assert_eq!(game.board(), &final_position);
+    }
+
+    #[test]
+    fn moves_after_navigating_back_replace_the_rest() {
+        let zobrist_table = ZobristTable::default();
+        let mut game = Game::default();
+
+        for uci in ["f2f3", "e7e5", "g2g4"] {
+            let r#move = game.board().parse_uci_move(uci).unwrap();
+            game.push_move(r#move, &zobrist_table).unwrap();
+        }
+
+        game.back(&zobrist_table);
+        let r#move = game.board().parse_uci_move("g2g3").unwrap();
+        game.push_move(r#move, &zobrist_table).unwrap();
+        assert_eq!(game.moves().len(), 3);
+        assert!(!game.forward(&zobrist_table));
+
+        // Fool's mate sets the result
+        game.back(&zobrist_table);
+        game.push_move(game.board().parse_uci_move("g2g4").unwrap(), &zobrist_table).unwrap();
+        game.push_move(game.board().parse_uci_move("d8h4").unwrap(), &zobrist_table).unwrap();
+        assert_eq!(game.result, GameResult::BlackWins);
+
+        let king_move = Move::new(Vector(FILE_E, RANK_1), Vector(FILE_F, RANK_2), MoveKind::Quiet);
+        assert_eq!(game.push_move(king_move, &zobrist_table), Err(IllegalMove::LeavesKingInCheck));
+        assert_eq!(game.moves().len(), 4);
+    }
+}
@@ -0,0 +1,276 @@
+use std::fmt::Display;
+
+use crate::chess::bitboard::{DARK_SQUARES, LIGHT_SQUARES};
+use crate::chess::board::{Board, Color, PieceType};
+use crate::chess::r#move::Move;
+
+const FIFTY_MOVE_RULE_PLIES: u16 = 100;
+const SEVENTY_FIVE_MOVE_RULE_PLIES: u16 = 150;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate(Color), // winner
+    Stalemate,
+    Draw(DrawReason),
+}
+
+/// Draws other than stalemate, which follow from the position or its history rather than from the moves available
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DrawReason {
+    ThreefoldRepetition,
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+    InsufficientMaterial,
+}
+
+impl GameStatus {
+    pub fn is_over(&self) -> bool {
+        *self != GameStatus::Ongoing
+    }
+}
+
+impl Board {
+    pub fn game_status(&self) -> GameStatus {
+        self.game_status_with_moves(&self.generate_legal_moves(self.next_player))
+    }
+
+    /// Game status for a position whose legal moves have already been generated, e.g. during search
+    pub fn game_status_with_moves(&self, legal_moves: &[Move]) -> GameStatus {
+        if legal_moves.is_empty() {
+            return if self.is_in_check(self.next_player) {
+                GameStatus::Checkmate(self.next_player.other())
+            } else {
+                GameStatus::Stalemate
+            };
+        }
+
+        // Claimable draws are treated as claimed
+        if self.is_insufficient_material() {
+            GameStatus::Draw(DrawReason::InsufficientMaterial)
+        } else if self.is_seventy_five_move_rule() {
+            GameStatus::Draw(DrawReason::SeventyFiveMoveRule)
+        } else if self.is_fifty_move_rule() {
+            GameStatus::Draw(DrawReason::FiftyMoveRule)
+        } else if self.is_threefold_repetition() {
+            GameStatus::Draw(DrawReason::ThreefoldRepetition)
+        } else {
+            GameStatus::Ongoing
+        }
+    }
+
+    /// Number of times the current position has occurred, including the current occurrence
+    pub fn repetition_count(&self) -> usize {
+        // Only positions since the last capture or pawn move can repeat.
+        // The last entry is the position before the last move, only every other position has the same player to move.
+        1 + self.hash_history.iter().rev()
+            .take(self.halfmove_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .filter(|hash| **hash == self.zobrist_hash)
+            .count()
+    }
+
+    /// The position occurred before, the search scores this as a draw as the repetition could be continued
+    pub fn is_repetition(&self) -> bool {
+        self.repetition_count() >= 2
+    }
+
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetition_count() >= 3
+    }
+
+    pub fn is_fifty_move_rule(&self) -> bool {
+        self.halfmove_clock >= FIFTY_MOVE_RULE_PLIES
+    }
+
+    pub fn is_seventy_five_move_rule(&self) -> bool {
+        self.halfmove_clock >= SEVENTY_FIVE_MOVE_RULE_PLIES
+    }
+
+    /// Dead positions where neither side can checkmate: bare kings, a single minor piece, or only bishops on squares of the same color
+    pub fn is_insufficient_material(&self) -> bool {
+        if self.piece_type_bitboard(PieceType::Pawn) | self.piece_type_bitboard(PieceType::Rook) | self.piece_type_bitboard(PieceType::Queen) != 0 {
+            return false;
+        }
+
+        let knights = self.piece_type_bitboard(PieceType::Knight);
+        let bishops = self.piece_type_bitboard(PieceType::Bishop);
+        let minor_pieces = (knights | bishops).count_ones();
+        let bishops_on_both_colors = bishops & LIGHT_SQUARES != 0 && bishops & DARK_SQUARES != 0;
+
+        minor_pieces <= 1 || (knights == 0 && !bishops_on_both_colors)
+    }
+}
+
+impl Display for GameStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameStatus::Ongoing => write!(f, "Ongoing"),
+            GameStatus::Checkmate(Color::White) => write!(f, "Checkmate - White wins"),
+            GameStatus::Checkmate(Color::Black) => write!(f, "Checkmate - Black wins"),
+            GameStatus::Stalemate => write!(f, "Stalemate"),
+            GameStatus::Draw(reason) => write!(f, "Draw - {reason}"),
+        }
+    }
+}
+
+impl Display for DrawReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrawReason::ThreefoldRepetition => write!(f, "threefold repetition"),
+            DrawReason::FiftyMoveRule => write!(f, "fifty-move rule"),
+            DrawReason::SeventyFiveMoveRule => write!(f, "seventy-five-move rule"),
+            DrawReason::InsufficientMaterial => write!(f, "insufficient material"),
+        }
+    }
+}
+
+/// Outcome of a game as recorded in PGN, games may also end by resignation, agreement or time
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unknown, // ongoing or abandoned
+}
+
+impl GameResult {
+    /// Parses the PGN result tokens "1-0", "0-1", "1/2-1/2" and "*"
+    pub fn from_pgn(token: &str) -> Option<GameResult> {
+        match token {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Unknown),
+            _ => None,
+        }
+    }
+}
+
+impl From<GameStatus> for GameResult {
+    fn from(status: GameStatus) -> Self {
+        match status {
+            GameStatus::Ongoing => GameResult::Unknown,
+            GameStatus::Checkmate(Color::White) => GameResult::WhiteWins,
+            GameStatus::Checkmate(Color::Black) => GameResult::BlackWins,
+            GameStatus::Stalemate | GameStatus::Draw(_) => GameResult::Draw,
+        }
+    }
+}
+
+impl Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameResult::WhiteWins => write!(f, "1-0"),
+            GameResult::BlackWins => write!(f, "0-1"),
+            GameResult::Draw => write!(f, "1/2-1/2"),
+            GameResult::Unknown => write!(f, "*"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::Piece;
+    use crate::chess::board::PieceType::*;
+    use crate::chess::r#move::MoveKind;
+    use crate::chess::vector::Vector;
+    use crate::chess::zobrist::ZobristTable;
+
+    #[test]
+    fn fools_mate_is_checkmate() {
+        let zobrist_table = ZobristTable::default();
+        let mut board = Board::default();
+
+        for (src, dst, kind) in [
+            (Vector(5, 6), Vector(5, 5), MoveKind::Quiet),
+            (Vector(4, 1), Vector(4, 3), MoveKind::DoublePawnPush),
+            (Vector(6, 6), Vector(6, 4), MoveKind::DoublePawnPush),
+        ] {
+            board.execute_move(Move::new(src, dst, kind), &zobrist_table);
+            assert_eq!(board.game_status(), GameStatus::Ongoing);
+        }
+
+        board.execute_move(Move::new(Vector(3, 0), Vector(7, 4), MoveKind::Quiet), &zobrist_table);
+        assert_eq!(board.game_status(), GameStatus::Checkmate(Color::Black));
+    }
+
+    #[test]
+    fn cornered_king_without_moves_is_stalemate() {
+        let mut board = Board::empty();
+        board.next_player = Color::Black;
+        board.set_piece(Vector(0, 0), Some(Piece::new(Color::Black, King)));
+        board.set_piece(Vector(1, 2), Some(Piece::new(Color::White, Queen)));
+        board.set_piece(Vector(2, 7), Some(Piece::new(Color::White, King)));
+
+        assert_eq!(board.game_status(), GameStatus::Stalemate);
+    }
+
+    fn kings_with(pieces: &[(Vector, Piece)]) -> Board {
+        let mut board = Board::empty();
+        board.set_piece(Vector(4, 7), Some(Piece::new(Color::White, King)));
+        board.set_piece(Vector(4, 0), Some(Piece::new(Color::Black, King)));
+
+        for (coord, piece) in pieces {
+            board.set_piece(*coord, Some(*piece));
+        }
+
+        board
+    }
+
+    #[test]
+    fn insufficient_material() {
+        assert!(kings_with(&[]).is_insufficient_material());
+        assert!(kings_with(&[(Vector(2, 7), Piece::new(Color::White, Bishop))]).is_insufficient_material());
+        assert!(kings_with(&[(Vector(1, 0), Piece::new(Color::Black, Knight))]).is_insufficient_material());
+
+        // Bishops on c1 and f8 are both on dark squares
+        let same_colored_bishops = kings_with(&[(Vector(2, 7), Piece::new(Color::White, Bishop)), (Vector(5, 0), Piece::new(Color::Black, Bishop))]);
+        assert!(same_colored_bishops.is_insufficient_material());
+        assert_eq!(same_colored_bishops.game_status(), GameStatus::Draw(DrawReason::InsufficientMaterial));
+        assert_eq!(same_colored_bishops.evaluate_position(), 0.0);
+
+        // Bishops on c1 and c8 are on opposite colors
+        assert!(!kings_with(&[(Vector(2, 7), Piece::new(Color::White, Bishop)), (Vector(2, 0), Piece::new(Color::Black, Bishop))]).is_insufficient_material());
+        assert!(!kings_with(&[(Vector(1, 7), Piece::new(Color::White, Knight)), (Vector(6, 7), Piece::new(Color::White, Knight))]).is_insufficient_material());
+        assert!(!kings_with(&[(Vector(0, 6), Piece::new(Color::White, Pawn))]).is_insufficient_material());
+    }
+
+    #[test]
+    fn knight_shuffle_is_threefold_repetition() {
+        let zobrist_table = ZobristTable::default();
+        let mut board = Board::default();
+
+        let shuffle = [
+            Move::new(Vector(6, 7), Vector(5, 5), MoveKind::Quiet),
+            Move::new(Vector(6, 0), Vector(5, 2), MoveKind::Quiet),
+            Move::new(Vector(5, 5), Vector(6, 7), MoveKind::Quiet),
+            Move::new(Vector(5, 2), Vector(6, 0), MoveKind::Quiet),
+        ];
+
+        for r#move in shuffle.iter().chain(shuffle.iter()) {
+            assert_eq!(board.game_status(), GameStatus::Ongoing);
+            board.execute_move(*r#move, &zobrist_table);
+        }
+
+        assert_eq!(board.repetition_count(), 3);
+        assert_eq!(board.game_status(), GameStatus::Draw(DrawReason::ThreefoldRepetition));
+    }
+
+    #[test]
+    fn pawn_move_resets_halfmove_clock() {
+        let zobrist_table = ZobristTable::default();
+        let mut board = Board::default();
+        board.halfmove_clock = 99;
+
+        board.execute_move(Move::new(Vector(6, 7), Vector(5, 5), MoveKind::Quiet), &zobrist_table);
+        assert_eq!(board.game_status(), GameStatus::Draw(DrawReason::FiftyMoveRule));
+
+        board.execute_move(Move::new(Vector(4, 1), Vector(4, 3), MoveKind::DoublePawnPush), &zobrist_table);
+        assert_eq!(board.halfmove_clock, 0);
+        assert_eq!(board.repetition_count(), 1);
+        assert_eq!(board.game_status(), GameStatus::Ongoing);
+    }
+}
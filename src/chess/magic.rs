@@ -0,0 +1,168 @@
+use std::sync::LazyLock;
+
+use rand::prelude::*;
+use rand_chacha::ChaCha20Rng;
+
+use crate::chess::bitboard::{square_bit, Bitboard};
+use crate::chess::r#move::{BISHOP_OFFSETS, ROOK_OFFSETS};
+use crate::chess::vector::Vector;
+
+// Fancy magic bitboards: the blockers relevant to a slider on a square are multiplied by a magic number,
+// whose high bits then form a collision free index into the table of attacks for that square.
+// The magics are searched for on first use, which takes a fraction of a second.
+
+static SLIDING_ATTACKS: LazyLock<SlidingAttacks> = LazyLock::new(SlidingAttacks::generate);
+
+#[inline]
+pub fn bishop_attacks(coord: Vector, occupancy: Bitboard) -> Bitboard {
+    let tables = &*SLIDING_ATTACKS;
+    tables.attacks[tables.bishop_magics[coord.index()].index(occupancy)]
+}
+
+#[inline]
+pub fn rook_attacks(coord: Vector, occupancy: Bitboard) -> Bitboard {
+    let tables = &*SLIDING_ATTACKS;
+    tables.attacks[tables.rook_magics[coord.index()].index(occupancy)]
+}
+
+#[derive(Clone, Copy, Default)]
+struct Magic {
+    mask: Bitboard, // relevant blockers, i.e. the rays without the board edges
+    magic: u64,
+    shift: u32,
+    offset: usize, // start of this square's attacks in the shared table
+}
+
+impl Magic {
+    #[inline]
+    fn index(&self, occupancy: Bitboard) -> usize {
+        self.offset + ((occupancy & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+struct SlidingAttacks {
+    bishop_magics: [Magic; 64],
+    rook_magics: [Magic; 64],
+    attacks: Vec<Bitboard>, // bishop and rook attacks of all squares
+}
+
+impl SlidingAttacks {
+    fn generate() -> Self {
+        let seed = [
+            0x6d, 0x61, 0x67, 0x69, 0x63, 0x20, 0x62, 0x69,
+            0x74, 0x62, 0x6f, 0x61, 0x72, 0x64, 0x73, 0x21,
+            0x3e, 0x9a, 0x0c, 0x51, 0xd7, 0x28, 0x84, 0xb3,
+            0x1f, 0x6e, 0xa2, 0x45, 0xc9, 0x70, 0x0b, 0xe8,
+        ];
+        let mut rng = ChaCha20Rng::from_seed(seed);
+
+        let mut attacks = vec![];
+        let bishop_magics = std::array::from_fn(|index| find_magic(Vector::from_index(index), &BISHOP_OFFSETS, &mut attacks, &mut rng));
+        let rook_magics = std::array::from_fn(|index| find_magic(Vector::from_index(index), &ROOK_OFFSETS, &mut attacks, &mut rng));
+
+        Self { bishop_magics, rook_magics, attacks }
+    }
+}
+
+/// Searches a magic for the slider on the square, appending its attack table to `attacks`
+fn find_magic(coord: Vector, offsets: &[Vector], attacks: &mut Vec<Bitboard>, rng: &mut ChaCha20Rng) -> Magic {
+    let mask = relevant_blockers(coord, offsets);
+    let bits = mask.count_ones();
+    let shift = 64 - bits;
+
+    // Enumerate all subsets of the mask (carry-rippler) together with their attacks
+    let mut blockers = Vec::with_capacity(1 << bits);
+    let mut subset: Bitboard = 0;
+    loop {
+        blockers.push((subset, ray_attacks(coord, offsets, subset)));
+        subset = subset.wrapping_sub(mask) & mask;
+        if subset == 0 {
+            break;
+        }
+    }
+
+    let offset = attacks.len();
+    let mut table: Vec<Bitboard> = vec![0; 1 << bits];
+    let mut filled_in_attempt: Vec<u32> = vec![0; 1 << bits]; // avoids clearing the table for every candidate
+
+    for attempt in 1.. {
+        let magic = rng.random::<u64>() & rng.random::<u64>() & rng.random::<u64>(); // sparse candidates work best
+        if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 {
+            continue;
+        }
+
+        let candidate = Magic { mask, magic, shift, offset: 0 };
+
+        // Different blockers may share an index, as long as they result in the same attacks
+        let collision_free = blockers.iter().all(|(subset, subset_attacks)| {
+            let index = candidate.index(*subset);
+            if filled_in_attempt[index] == attempt {
+                table[index] == *subset_attacks
+            } else {
+                filled_in_attempt[index] = attempt;
+                table[index] = *subset_attacks;
+                true
+            }
+        });
+
+        if collision_free {
+            attacks.extend_from_slice(&table);
+            return Magic { offset, ..candidate };
+        }
+    }
+
+    unreachable!()
+}
+
+/// Blockers on the last square of a ray don't change the attacks, so they are left out
+fn relevant_blockers(coord: Vector, offsets: &[Vector]) -> Bitboard {
+    let mut mask = 0;
+
+    for offset in offsets {
+        let mut dst = coord + *offset;
+        while (dst + *offset).is_on_board() {
+            mask |= square_bit(dst);
+            dst = dst + *offset;
+        }
+    }
+
+    mask
+}
+
+/// Walks each ray until the first occupied square, which is included as it might be captured
+fn ray_attacks(coord: Vector, offsets: &[Vector], occupancy: Bitboard) -> Bitboard {
+    let mut attacks = 0;
+
+    for offset in offsets {
+        let mut dst = coord + *offset;
+        while dst.is_on_board() {
+            let bit = square_bit(dst);
+            attacks |= bit;
+            if occupancy & bit != 0 {
+                break;
+            }
+            dst = dst + *offset;
+        }
+    }
+
+    attacks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_lookups_match_ray_walking() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+
+        for index in 0..64 {
+            let coord = Vector::from_index(index);
+            for _ in 0..100 {
+                let occupancy = rng.random::<u64>() & rng.random::<u64>();
+                assert_eq!(bishop_attacks(coord, occupancy), ray_attacks(coord, &BISHOP_OFFSETS, occupancy));
+                assert_eq!(rook_attacks(coord, occupancy), ray_attacks(coord, &ROOK_OFFSETS, occupancy));
+            }
+        }
+    }
+}
@@ -1,42 +1,97 @@
-use crate::chess::{board::{Board, Piece}, r#move::{Move, MoveKind}, vector::Vector};
+use crate::chess::{board::{Board, Color, Piece}, r#move::{Move, MoveKind}, vector::Vector};
 use crate::chess::board::Color::White;
-use crate::chess::board::PieceType::*;
 use crate::chess::zobrist::ZobristTable;
 
-#[allow(dead_code)]
 pub mod board;
-#[allow(dead_code)]
 pub mod r#move;
-#[allow(dead_code)]
+pub mod move_list;
 pub mod vector;
-#[allow(dead_code)]
 pub mod evaluation;
+pub mod game_status;
+pub mod fen;
+pub mod bitboard;
+mod magic;
+pub mod perft;
+pub mod validation;
+pub mod uci;
+pub mod san;
+pub mod pgn;
+pub mod game;
+pub mod epd;
+pub mod chess960;
 
+#[cfg(feature = "gui")]
 pub mod visualizer;
-mod negamax;
-mod zobrist;
+pub mod negamax;
+pub mod zobrist;
 mod transposition;
 mod ordering;
 
+/// Everything `make_move` changes that can't be derived from the move itself, used to restore the board in `unmake_move`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MoveUndo {
+    r#move: Move,
+    moved_piece: Piece, // before the move, i.e. with the original moved flag and before promotion
+    captured: Option<(Vector, Piece)>, // the coordinate differs from the move destination for en passant
+    castled_rook: Option<Piece>,
+    left_castling_rights: [bool; 2],
+    right_castling_rights: [bool; 2],
+    last_move: Option<Move>,
+    zobrist_hash: u64,
+    halfmove_clock: u16,
+    fullmove_number: u16,
+}
+
 impl Board {
     pub fn execute_move(&mut self, r#move: Move, zobrist_table: &ZobristTable) {
+        self.make_move(r#move, zobrist_table);
+    }
+
+    /// Executes the move, returning the information needed to take it back with `unmake_move`
+    pub fn make_move(&mut self, r#move: Move, zobrist_table: &ZobristTable) -> MoveUndo {
         let player = self.next_player;
+        let previous_en_passant_file = self.en_passant_hash_file();
 
-        let src_square = self.piece_at_mut(r#move.src);
-        let mut piece = src_square.expect("expected move to be valid");
+        // remove src piece
+        let mut piece = self.take_piece(r#move.src()).expect("expected move to be valid");
         let moved_before = piece.moved();
 
+        let mut undo = MoveUndo {
+            r#move,
+            moved_piece: piece,
+            captured: None,
+            castled_rook: None,
+            left_castling_rights: self.left_castling_rights,
+            right_castling_rights: self.right_castling_rights,
+            last_move: self.last_move,
+            zobrist_hash: self.zobrist_hash,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+        };
+
         piece.set_moved();
 
-        // remove src piece
-        *src_square = None;
+        // Fifty-move rule and repetition tracking, only positions since the last capture or pawn move can repeat
+        if piece.is_pawn() || r#move.is_capture_with_target() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        self.hash_history.push(self.zobrist_hash);
 
         // Update zobrist hash for move
-        self.zobrist_hash ^= zobrist_table.piece_key(&r#move.src, &piece); // source
+        self.zobrist_hash ^= zobrist_table.piece_key(&r#move.src(), &piece); // source
         if r#move.is_capture_with_target() { // victim
-            self.zobrist_hash ^= zobrist_table.piece_key(&r#move.dst, &self.piece_at(r#move.dst).unwrap()); // capture moves expects piece to be present
+            let victim = self.piece_at(r#move.dst()).unwrap(); // capture moves expects piece to be present
+            self.zobrist_hash ^= zobrist_table.piece_key(&r#move.dst(), &victim);
+            undo.captured = Some((r#move.dst(), victim));
+
+            // A rook captured on its start square can no longer castle
+            if victim.is_rook() {
+                self.revoke_rook_castling_rights(victim.color(), r#move.dst(), zobrist_table);
+            }
         }
-        self.zobrist_hash ^= zobrist_table.piece_key(&r#move.dst, &piece); // destination
+        self.zobrist_hash ^= zobrist_table.piece_key(&r#move.dst(), &piece); // destination
 
         // Castling rights + zobrist
         if piece.is_king() && !moved_before {
@@ -51,85 +106,120 @@ impl Board {
             }
         }
         if piece.is_rook() && !moved_before {
-            if r#move.src.0 == 0 && self.left_castling_rights[player.zobrist_index()] {
-                self.left_castling_rights[player.zobrist_index()] = false;
-                self.zobrist_hash ^= zobrist_table.left_castle[self.next_player.zobrist_index()];
-            } else if self.right_castling_rights[player.zobrist_index()] {
-                self.right_castling_rights[player.zobrist_index()] = false;
-                self.zobrist_hash ^= zobrist_table.right_castle[self.next_player.zobrist_index()];
-            }
+            self.revoke_rook_castling_rights(player, r#move.src(), zobrist_table);
         }
 
-        // Move to destination
-        let dst_square = self.piece_at_mut(r#move.dst);
-        *dst_square = Some(piece);
-
-        // Castling - not recorded as last move, as the king move encodes all information
-        if r#move.kind == MoveKind::QueenCastle {
-            self.execute_castle(&r#move, 0, 3, zobrist_table);
-        } else if r#move.kind == MoveKind::KingCastle {
-            self.execute_castle(&r#move, 7, 5, zobrist_table);
+        // Castling - not recorded as last move, as the king move encodes all information.
+        // The rook moves first, as in Chess960 it may start on the king's destination.
+        if let Some((rook_src, rook_dst)) = self.castling_rook_move(&r#move) {
+            undo.castled_rook = Some(self.execute_castle(rook_src, rook_dst, zobrist_table));
         }
 
-        // En passant
-        if r#move.kind == MoveKind::EPCapture {
-            let captured_coord = r#move.dst + if r#move.dst.1 == 5 { Vector(0, -1) } else { Vector(0, 1) };
-            let captured_square = self.piece_at_mut(captured_coord);
-            let captured_piece = captured_square.unwrap(); // capture -> there needs to be a piece there
+        // Move to destination
+        self.set_piece(r#move.dst(), Some(piece));
 
-            *captured_square = None;
+        // En passant
+        if r#move.kind() == MoveKind::EPCapture {
+            let captured_coord = r#move.dst() + if r#move.dst().1 == 5 { Vector(0, -1) } else { Vector(0, 1) };
+            let captured_piece = self.take_piece(captured_coord).unwrap(); // capture -> there needs to be a piece there
 
             self.zobrist_hash ^= zobrist_table.piece_key(&captured_coord, &captured_piece);
+            undo.captured = Some((captured_coord, captured_piece));
         }
 
-        // En passant rights - Zobrist Hashing
-        if r#move.kind == MoveKind::DoublePawnPush {
-            self.zobrist_hash ^= zobrist_table.en_passant_file[r#move.src.0 as usize]; // enable
-        }
-        if let Some(last_move) = self.last_move {
-            if last_move.kind == MoveKind::DoublePawnPush {
-                self.zobrist_hash ^= zobrist_table.en_passant_file[last_move.src.0 as usize]; // disable
-            }
+        // En passant rights - Zobrist Hashing, the new ones are added once the opponent is to move
+        if let Some(file) = previous_en_passant_file {
+            self.zobrist_hash ^= zobrist_table.en_passant_file[file as usize]; // disable
         }
 
         // Promotion
-        let promote_to = match r#move.kind {
-            MoveKind::PromotionKnight => Some(Knight),
-            MoveKind::PromotionBishop => Some(Bishop),
-            MoveKind::PromotionRook => Some(Rook),
-            MoveKind::PromotionQueen => Some(Queen),
-            MoveKind::CapturePromotionKnight => Some(Knight),
-            MoveKind::CapturePromotionBishop => Some(Bishop),
-            MoveKind::CapturePromotionRook => Some(Rook),
-            MoveKind::CapturePromotionQueen => Some(Queen),
-            _ => None,
-        };
-        if let Some(new_piece_type) = promote_to {
+        if let Some(new_piece_type) = r#move.kind().promotion_piece() {
             let mut new_piece = Piece::new(piece.color(), new_piece_type);
             new_piece.set_moved();
-            *self.piece_at_mut(r#move.dst) = Some(new_piece);
+            self.set_piece(r#move.dst(), Some(new_piece));
 
             // Update zobrist hash
-            self.zobrist_hash ^= zobrist_table.piece_key(&r#move.dst, &piece); // remove moved
-            self.zobrist_hash ^= zobrist_table.piece_key(&r#move.dst, &new_piece); // add promoted
+            self.zobrist_hash ^= zobrist_table.piece_key(&r#move.dst(), &piece); // remove moved
+            self.zobrist_hash ^= zobrist_table.piece_key(&r#move.dst(), &new_piece); // add promoted
         }
 
         self.last_move = Some(r#move);
 
         debug_assert_eq!(piece.color(), self.next_player); // assert that the move was executed by the current player
 
+        if self.next_player == Color::Black {
+            self.fullmove_number += 1;
+        }
         self.next_player = self.next_player.other();
         self.zobrist_hash ^= zobrist_table.black_to_move_key;
+
+        if let Some(file) = self.en_passant_hash_file() {
+            self.zobrist_hash ^= zobrist_table.en_passant_file[file as usize]; // enable
+        }
+
+        debug_assert_eq!(self.zobrist_hash, self.compute_zobrist_hash(zobrist_table), "incrementally updated zobrist hash diverged after {move:?}");
+
+        undo
+    }
+
+    /// Takes back the last move made with `make_move`, restoring the board exactly
+    pub fn unmake_move(&mut self, undo: MoveUndo) {
+        let r#move = undo.r#move;
+        let castling_rook_move = self.castling_rook_move(&r#move);
+
+        // Both castling pieces are lifted before either is put back, as in Chess960 their squares may overlap
+        self.take_piece(r#move.dst());
+        if let Some((_, rook_dst)) = castling_rook_move {
+            self.take_piece(rook_dst);
+        }
+        if let Some((captured_coord, captured_piece)) = undo.captured {
+            self.set_piece(captured_coord, Some(captured_piece));
+        }
+        self.set_piece(r#move.src(), Some(undo.moved_piece));
+        if let Some((rook_src, _)) = castling_rook_move {
+            self.set_piece(rook_src, undo.castled_rook);
+        }
+
+        self.left_castling_rights = undo.left_castling_rights;
+        self.right_castling_rights = undo.right_castling_rights;
+        self.last_move = undo.last_move;
+        self.zobrist_hash = undo.zobrist_hash;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.hash_history.pop();
+        self.next_player = self.next_player.other();
     }
 
+    /// Removes the castling rights belonging to a rook leaving (or being captured on) the given square
+    fn revoke_rook_castling_rights(&mut self, color: Color, coord: Vector, zobrist_table: &ZobristTable) {
+        let y = if color == White { 7 } else { 0 };
+
+        if coord == Vector(self.castling_files.left_rook, y) && self.left_castling_rights[color.zobrist_index()] {
+            self.left_castling_rights[color.zobrist_index()] = false;
+            self.zobrist_hash ^= zobrist_table.left_castle[color.zobrist_index()];
+        } else if coord == Vector(self.castling_files.right_rook, y) && self.right_castling_rights[color.zobrist_index()] {
+            self.right_castling_rights[color.zobrist_index()] = false;
+            self.zobrist_hash ^= zobrist_table.right_castle[color.zobrist_index()];
+        }
+    }
+
+    /// Source and destination of the rook for castling moves
     #[inline]
-    fn execute_castle(&mut self, r#move: &Move, rook_src_x: i8, rook_dst_x: i8, zobrist_table: &ZobristTable) {
-        let src = Vector(rook_src_x, r#move.src.1);
-        let dst = Vector(rook_dst_x, r#move.src.1);
+    pub fn castling_rook_move(&self, r#move: &Move) -> Option<(Vector, Vector)> {
+        let y = r#move.src().1;
 
-        let rook = self.piece_at_mut(src);
-        let mut rook_piece = rook.expect("expected rook to be present for requested castling");
-        *rook = None;
+        match r#move.kind() {
+            MoveKind::QueenCastle => Some((Vector(self.castling_files.left_rook, y), Vector(3, y))),
+            MoveKind::KingCastle => Some((Vector(self.castling_files.right_rook, y), Vector(5, y))),
+            _ => None,
+        }
+    }
+
+    /// Moves the rook for castling, returning it as it was before the move
+    #[inline]
+    fn execute_castle(&mut self, src: Vector, dst: Vector, zobrist_table: &ZobristTable) -> Piece {
+        let mut rook_piece = self.take_piece(src).expect("expected rook to be present for requested castling");
+        let original_rook_piece = rook_piece;
 
         rook_piece.set_moved();
 
@@ -139,6 +229,42 @@ impl Board {
         self.zobrist_hash ^= zobrist_table.piece_key(&dst, &rook_piece);
 
 
-        *self.piece_at_mut(dst) = Some(rook_piece);
+        self.set_piece(dst, Some(rook_piece));
+
+        original_rook_piece
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unmake_move_restores_board() {
+        let zobrist_table = ZobristTable::default();
+
+        // castling, en passant, promotions and captures of castling rooks
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            let mut board = Board::from_fen(fen).unwrap();
+            let original = board.clone();
+
+            for r#move in board.generate_legal_moves(board.next_player) {
+                let undo = board.make_move(r#move, &zobrist_table);
+                let after_move = board.clone();
+
+                for reply in board.generate_legal_moves(board.next_player) {
+                    let reply_undo = board.make_move(reply, &zobrist_table);
+                    board.unmake_move(reply_undo);
+                    assert_eq!(board, after_move, "unmaking {reply:?} after {move:?} in {fen}");
+                }
+
+                board.unmake_move(undo);
+                assert_eq!(board, original, "unmaking {move:?} in {fen}");
+            }
+        }
     }
-}
\ No newline at end of file
+}
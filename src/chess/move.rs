@@ -1,18 +1,47 @@
-use std::vec;
-
-use crate::chess::{board::{Board, Color, Piece}, vector::Vector};
+use crate::chess::{board::{Board, Color, Piece, PieceType}, vector::Vector};
+use crate::chess::move_list::MoveList;
+use crate::chess::bitboard::{bishop_attacks, pawn_attacks, rook_attacks, rank_span, square_bit, Bitboard, BitboardIter, KING_ATTACKS, KNIGHT_ATTACKS};
 use crate::chess::board::PieceType::*;
 use crate::chess::board::Color::*;
 
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub struct Move {
-    pub src: Vector,
-    pub dst: Vector,
-    pub kind: MoveKind, // this could be packaged in 16 bits total, spending time shifting bits for obtaining the coordinate indices
+/// Packed into 16 bits: source square index in bits 0-5, destination square index in bits 6-11 and the kind in bits 12-15
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move(u16);
+
+impl Move {
+    #[inline]
+    pub const fn new(src: Vector, dst: Vector, kind: MoveKind) -> Self {
+        Move(src.index() as u16 | (dst.index() as u16) << 6 | (kind as u16) << 12)
+    }
+
+    #[inline]
+    pub const fn src(&self) -> Vector {
+        Vector::from_index((self.0 & 0x3f) as usize)
+    }
+
+    #[inline]
+    pub const fn dst(&self) -> Vector {
+        Vector::from_index((self.0 >> 6 & 0x3f) as usize)
+    }
+
+    #[inline]
+    pub const fn kind(&self) -> MoveKind {
+        MoveKind::ALL[(self.0 >> 12) as usize]
+    }
+}
+
+impl std::fmt::Debug for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Move")
+            .field("src", &self.src())
+            .field("dst", &self.dst())
+            .field("kind", &self.kind())
+            .finish()
+    }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
 #[repr(u8)]
 pub enum MoveKind {
     Quiet,
@@ -32,40 +61,121 @@ pub enum MoveKind {
 }
 
 impl MoveKind {
-    fn is_capture_with_target(&self) -> bool {
+    /// The piece a pawn is promoted to, if this is a promotion
+    pub fn promotion_piece(&self) -> Option<PieceType> {
         match self {
-            MoveKind::Capture => true,
-            MoveKind::CapturePromotionKnight => true,
-            MoveKind::CapturePromotionBishop => true,
-            MoveKind::CapturePromotionRook => true,
-            MoveKind::CapturePromotionQueen => true,
-            _ => false
+            MoveKind::PromotionKnight | MoveKind::CapturePromotionKnight => Some(Knight),
+            MoveKind::PromotionBishop | MoveKind::CapturePromotionBishop => Some(Bishop),
+            MoveKind::PromotionRook | MoveKind::CapturePromotionRook => Some(Rook),
+            MoveKind::PromotionQueen | MoveKind::CapturePromotionQueen => Some(Queen),
+            _ => None,
         }
     }
+
+    /// All kinds in discriminant order, used to decode packed moves
+    const ALL: [MoveKind; 14] = [
+        MoveKind::Quiet,
+        MoveKind::DoublePawnPush,
+        MoveKind::KingCastle,
+        MoveKind::QueenCastle,
+        MoveKind::Capture,
+        MoveKind::EPCapture,
+        MoveKind::PromotionKnight,
+        MoveKind::PromotionBishop,
+        MoveKind::PromotionRook,
+        MoveKind::PromotionQueen,
+        MoveKind::CapturePromotionKnight,
+        MoveKind::CapturePromotionBishop,
+        MoveKind::CapturePromotionRook,
+        MoveKind::CapturePromotionQueen,
+    ];
+
+    fn is_capture_with_target(&self) -> bool {
+        matches!(self,
+            MoveKind::Capture |
+            MoveKind::CapturePromotionKnight |
+            MoveKind::CapturePromotionBishop |
+            MoveKind::CapturePromotionRook |
+            MoveKind::CapturePromotionQueen
+        )
+    }
 }
 
+pub const KNIGHT_OFFSETS: [Vector; 8] = [
+    Vector(-2, -1), Vector(-1, -2),
+    Vector( 2, -1), Vector(-1,  2),
+    Vector(-2,  1), Vector( 1, -2),
+    Vector( 2,  1), Vector( 1,  2),
+];
+
+pub const BISHOP_OFFSETS: [Vector; 4] = [
+    Vector(-1, -1),
+    Vector( 1, -1),
+    Vector(-1,  1),
+    Vector( 1,  1),
+];
+
+pub const ROOK_OFFSETS: [Vector; 4] = [
+    Vector( 0, -1),
+    Vector( 0,  1),
+    Vector(-1,  0),
+    Vector( 1,  0),
+];
+
+pub const KING_OFFSETS: [Vector; 8] = [
+    Vector( 0,  1),
+    Vector( 1,  1),
+    Vector( 1,  0),
+    Vector( 1, -1),
+    Vector( 0, -1),
+    Vector(-1, -1),
+    Vector(-1,  0),
+    Vector(-1,  1),
+];
+
 impl Board {
-    pub fn generate_moves(&self, player: Color) -> Vec<Move> { // TODO: check for legal, this is just pseudo legal
-        self.coords_with_piece_of_color(player)
-            .flat_map(|coord| self.generate_piece_moves(coord).into_iter())
-            .collect()
+    /// Legal moves only, i.e. pseudo legal moves that don't leave the own king in check
+    pub fn generate_legal_moves(&self, player: Color) -> MoveList {
+        let mut moves = self.generate_moves(player);
+        moves.retain(|r#move| !self.leaves_king_in_check(r#move));
+        moves
     }
 
-    pub fn generate_piece_moves(&self, coord: Vector) -> Vec<Move> {
+    pub fn generate_legal_piece_moves(&self, coord: Vector) -> MoveList {
+        let mut moves = self.generate_piece_moves(coord);
+        moves.retain(|r#move| !self.leaves_king_in_check(r#move));
+        moves
+    }
+
+    /// Pseudo legal moves, these may leave the own king in check
+    pub fn generate_moves(&self, player: Color) -> MoveList {
+        let mut moves = MoveList::new();
+        for coord in self.coords_with_piece_of_color(player) {
+            self.add_piece_moves(coord, &mut moves);
+        }
+        moves
+    }
+
+    pub fn generate_piece_moves(&self, coord: Vector) -> MoveList {
+        let mut moves = MoveList::new();
+        self.add_piece_moves(coord, &mut moves);
+        moves
+    }
+
+    /// Pushes the pseudo legal moves of the piece on the square to the list
+    pub fn add_piece_moves(&self, coord: Vector, moves: &mut MoveList) {
         let piece = self.piece_at(coord).expect("Only coords with piece can be supplied");
         match piece.piece_type() {
-            Pawn => self.generate_pawn_moves(coord, piece),
-            Knight => self.generate_knight_moves(coord, piece),
-            Bishop => self.generate_bishop_moves(coord, piece),
-            Rook => self.generate_rook_moves(coord, piece),
-            Queen => self.generate_queen_moves(coord, piece),
-            King => self.generate_king_moves(coord, piece),
+            Pawn => self.generate_pawn_moves(coord, piece, moves),
+            Knight => self.generate_knight_moves(coord, piece, moves),
+            Bishop => self.generate_bishop_moves(coord, piece, moves),
+            Rook => self.generate_rook_moves(coord, piece, moves),
+            Queen => self.generate_queen_moves(coord, piece, moves),
+            King => self.generate_king_moves(coord, piece, moves),
         }
     }
 
-    pub fn generate_pawn_moves(&self, coord: Vector, piece: Piece) -> Vec<Move> {
-        let mut moves = vec![];
-
+    pub fn generate_pawn_moves(&self, coord: Vector, piece: Piece, moves: &mut MoveList) {
         let y_offset = if piece.color() == White {
             Vector(0, -1)
         } else {
@@ -78,16 +188,16 @@ impl Board {
         if pawn_push_coord.is_on_board() && self.piece_at(pawn_push_coord).is_none() {
             if pawn_push_coord.1 == 0 || pawn_push_coord.1 == 7 {
                 for kind in [MoveKind::PromotionKnight, MoveKind::PromotionBishop, MoveKind::PromotionRook, MoveKind::PromotionQueen] {
-                    moves.push(Move { src: coord, dst: pawn_push_coord, kind })
+                    moves.push(Move::new(coord, pawn_push_coord, kind))
                 }
             } else {
-                moves.push(Move { src: coord, dst: pawn_push_coord, kind: MoveKind::Quiet });
+                moves.push(Move::new(coord, pawn_push_coord, MoveKind::Quiet));
             }
             
             if (piece.color() == White && coord.1 == 6) || (piece.color() == Black && coord.1 == 1) {
                 let pawn_double_push_coord = coord + y_offset * 2;
                 if pawn_double_push_coord.is_on_board() && self.piece_at(pawn_double_push_coord).is_none() {
-                    moves.push(Move { src: coord, dst: pawn_double_push_coord, kind: MoveKind::DoublePawnPush });
+                    moves.push(Move::new(coord, pawn_double_push_coord, MoveKind::DoublePawnPush));
                 }
             }
         }
@@ -106,17 +216,17 @@ impl Board {
                 if victim.color() != piece.color() {
                     if pawn_push_coord.1 == 0 || pawn_push_coord.1 == 7 {
                         for kind in [MoveKind::CapturePromotionKnight, MoveKind::CapturePromotionBishop, MoveKind::CapturePromotionRook, MoveKind::CapturePromotionQueen] {
-                            moves.push(Move { src: coord, dst: attack_coord, kind })
+                            moves.push(Move::new(coord, attack_coord, kind))
                         }
                     } else {
-                        moves.push(Move { src: coord, dst: attack_coord, kind: MoveKind::Capture });
+                        moves.push(Move::new(coord, attack_coord, MoveKind::Capture));
                     }
                 }
             }
         }
 
         // en passant
-        if let Some(Move { kind: MoveKind::DoublePawnPush, dst: last_move_dst, .. }) = self.last_move {
+        if let Some(last_move_dst) = self.last_move.filter(|last_move| last_move.kind() == MoveKind::DoublePawnPush).map(|last_move| last_move.dst()) {
             for attack_coord in attack_coords {
                 if attack_coord.is_on_board() {
                     let attacked_piece_coord = Vector(attack_coord.0, coord.1);
@@ -125,175 +235,314 @@ impl Board {
                     };
 
                     if victim.color() != piece.color() && last_move_dst == attacked_piece_coord {
-                        moves.push(Move { src: coord, dst: attack_coord, kind: MoveKind::EPCapture });
+                        moves.push(Move::new(coord, attack_coord, MoveKind::EPCapture));
                     }
                 }
             }
         }
+    }
 
-        moves
+    pub fn generate_knight_moves(&self, coord: Vector, piece: Piece, moves: &mut MoveList) {
+        self.generate_moves_to_targets(coord, piece, KNIGHT_ATTACKS[coord.index()], moves);
     }
 
-    pub fn generate_knight_moves(&self, coord: Vector, piece: Piece) -> Vec<Move> {
-        let mut moves = vec![];
+    pub fn generate_bishop_moves(&self, coord: Vector, piece: Piece, moves: &mut MoveList) {
+        self.generate_moves_to_targets(coord, piece, bishop_attacks(coord, self.occupancy()), moves);
+    }
 
-        let offsets = [
-            Vector(-2, -1), Vector(-1, -2),
-            Vector( 2, -1), Vector(-1,  2),
-            Vector(-2,  1), Vector( 1, -2),
-            Vector( 2,  1), Vector( 1,  2),
-        ];
+    pub fn generate_rook_moves(&self, coord: Vector, piece: Piece, moves: &mut MoveList) {
+        self.generate_moves_to_targets(coord, piece, rook_attacks(coord, self.occupancy()), moves);
+    }
 
-        let dsts = offsets.into_iter().map(|off| coord + off);
+    pub fn generate_queen_moves(&self, coord: Vector, piece: Piece, moves: &mut MoveList) {
+        let occupancy = self.occupancy();
+        self.generate_moves_to_targets(coord, piece, bishop_attacks(coord, occupancy) | rook_attacks(coord, occupancy), moves);
+    }
 
-        moves.append(&mut self.generate_moves_to_squares(coord, piece, dsts));
+    pub fn generate_king_moves(&self, coord: Vector, piece: Piece, moves: &mut MoveList) {
+        self.generate_moves_to_targets(coord, piece, KING_ATTACKS[coord.index()], moves);
 
-        moves
-    }
+        // Castling - the king may not castle out of, through or into check.
+        // Only the king and the castling rook may stand on the squares either of them passes, which covers Chess960 as well.
+        let y: i8 = if piece.color() == White { 7 } else { 0 };
+        if coord != Vector(self.castling_files.king, y) {
+            return;
+        }
 
-    pub fn generate_bishop_moves(&self, coord: Vector, piece: Piece) -> Vec<Move> {
-        let mut moves = vec![];
+        for (rights, kind) in [
+            (self.left_castling_rights, MoveKind::QueenCastle),
+            (self.right_castling_rights, MoveKind::KingCastle),
+        ] {
+            if !rights[piece.color().zobrist_index()] {
+                continue;
+            }
 
-        let offsets = [
-            Vector(-1, -1),
-            Vector( 1, -1),
-            Vector(-1,  1),
-            Vector( 1,  1),
-        ];
+            let r#move = Move::new(coord, Vector(if kind == MoveKind::QueenCastle { 2 } else { 6 }, y), kind);
+            let (rook_src, rook_dst) = self.castling_rook_move(&r#move).expect("expected castling move");
 
-        moves.append(&mut self.generate_continous_moves_from_offsets(coord, piece, offsets));
+            let our_rook = self.piece_at(rook_src).is_some_and(|rook_piece| rook_piece.piece_type() == Rook && rook_piece.color() == piece.color());
+            let others = self.occupancy() & !square_bit(coord) & !square_bit(rook_src);
+            let empty_between = (rank_span(coord, r#move.dst()) | rank_span(rook_src, rook_dst)) & others == 0;
+            let king_safe = BitboardIter(rank_span(coord, r#move.dst())).all(|square| !self.is_square_attacked(square, piece.color().other()));
 
-        moves
+            if our_rook && empty_between && king_safe {
+                moves.push(r#move);
+            }
+        }
     }
 
-    pub fn generate_rook_moves(&self, coord: Vector, piece: Piece) -> Vec<Move> {
-        let mut moves = vec![];
+    /// Quiet moves and captures to all target squares not occupied by the own color
+    #[inline]
+    fn generate_moves_to_targets(&self, coord: Vector, piece: Piece, targets: Bitboard, moves: &mut MoveList) {
+        let opponent_pieces = self.color_bitboard(piece.color().other());
 
-        let offsets = [
-            Vector( 0, -1),
-            Vector( 0,  1),
-            Vector(-1,  0),
-            Vector( 1,  0),
-        ];
+        for dst in BitboardIter(targets & !self.color_bitboard(piece.color())) {
+            let kind = if opponent_pieces & square_bit(dst) != 0 { MoveKind::Capture } else { MoveKind::Quiet };
+            moves.push(Move::new(coord, dst, kind));
+        }
+    }
+}
 
-        moves.append(&mut self.generate_continous_moves_from_offsets(coord, piece, offsets));
+impl Board {
+    /// Checks whether executing the pseudo legal move would leave the moving player's king attacked.
+    /// Pins and double checks are handled implicitly, as the attacks are computed on the occupancy after the move.
+    pub(crate) fn leaves_king_in_check(&self, r#move: &Move) -> bool {
+        let piece = self.piece_at(r#move.src()).expect("expected move to be valid");
+        let player = piece.color();
+
+        // Captured pieces no longer attack, the pawn captured en passant is not on the destination square
+        let dst_bit = square_bit(r#move.dst());
+        let captured_bit = if r#move.kind() == MoveKind::EPCapture { square_bit(Vector(r#move.dst().0, r#move.src().1)) } else { dst_bit };
+        let mut occupancy = (self.occupancy() & !square_bit(r#move.src()) & !captured_bit) | dst_bit;
+
+        // In Chess960 the castling rook may have shielded the king's destination
+        if let Some((rook_src, rook_dst)) = self.castling_rook_move(r#move) {
+            occupancy = (occupancy & !square_bit(rook_src)) | square_bit(rook_dst) | dst_bit;
+        }
 
-        moves
-    }
+        let king_coord = if piece.is_king() {
+            Some(r#move.dst())
+        } else {
+            self.king_coord(player)
+        };
 
-    pub fn generate_queen_moves(&self, coord: Vector, piece: Piece) -> Vec<Move> {
-        let mut moves = vec![];
-        
-        moves.append(&mut self.generate_bishop_moves(coord, piece));
-        moves.append(&mut self.generate_rook_moves(coord, piece));
+        king_coord.is_some_and(|coord| self.attackers_to(coord, player.other(), occupancy) & !captured_bit != 0)
+    }
 
-        moves
+    pub fn king_coord(&self, color: Color) -> Option<Vector> {
+        BitboardIter(self.piece_bitboard(King, color)).next()
     }
 
-    pub fn generate_king_moves(&self, coord: Vector, piece: Piece) -> Vec<Move> {
-        let mut moves = vec![];
+    pub fn is_in_check(&self, color: Color) -> bool {
+        self.king_coord(color).is_some_and(|coord| self.is_square_attacked(coord, color.other()))
+    }
 
-        let offsets = [
-            Vector( 0,  1),
-            Vector( 1,  1),
-            Vector( 1,  0),
-            Vector( 1, -1),
-            Vector( 0, -1),
-            Vector(-1, -1),
-            Vector(-1,  0),
-            Vector(-1,  1),
-        ];
+    pub fn is_square_attacked(&self, square: Vector, by: Color) -> bool {
+        self.attackers_to(square, by, self.occupancy()) != 0
+    }
 
-        let dsts = offsets.into_iter().map(|off| coord + off);
+    /// Coordinates of all pieces of the given color attacking the square
+    pub fn attackers_of(&self, square: Vector, by: Color) -> impl Iterator<Item=Vector> + use<'_> {
+        BitboardIter(self.attackers_to(square, by, self.occupancy()))
+    }
 
-        moves.append(&mut self.generate_moves_to_squares(coord, piece, dsts));
+    /// Pieces of the given color attacking the square, with sliders blocked by the given occupancy.
+    /// Each piece type's attacks from the square hit exactly the pieces of that type attacking it.
+    pub fn attackers_to(&self, square: Vector, by: Color, occupancy: Bitboard) -> Bitboard {
+        let queens = self.piece_bitboard(Queen, by);
 
-        // Castling - TODO: missing check for blocking checks
-        let y: i8 = if piece.color() == White { 7 } else { 0 };
+        (pawn_attacks(square, by.other()) & self.piece_bitboard(Pawn, by))
+            | (KNIGHT_ATTACKS[square.index()] & self.piece_bitboard(Knight, by))
+            | (KING_ATTACKS[square.index()] & self.piece_bitboard(King, by))
+            | (bishop_attacks(square, occupancy) & (self.piece_bitboard(Bishop, by) | queens))
+            | (rook_attacks(square, occupancy) & (self.piece_bitboard(Rook, by) | queens))
+    }
+}
 
-        // Castle left (queen side)
-        if self.left_castling_rights[piece.color().zobrist_index()] {
-            if let (Some(king_piece), Some(rook_piece)) = (self.piece_at(Vector(4, y)), self.piece_at(Vector(0, y))) {
-                let our_king = king_piece.piece_type() == King && king_piece.color() == piece.color();
-                let our_rook = rook_piece.piece_type() == Rook && rook_piece.color() == piece.color();
-                let empty_between = self.piece_at(Vector(1, y)).is_none() && self.piece_at(Vector(2, y)).is_none() && self.piece_at(Vector(3, y)).is_none(); // TODO: no checks on these squares
-                if our_king && our_rook && empty_between {
-                    moves.push(Move { src: coord, dst: Vector(2, y), kind: MoveKind::QueenCastle });
-                }
-            }
+impl Move {
+    #[inline]
+    pub fn is_capture_king(&self, board: &Board) -> bool {
+        if !self.is_capture_with_target() {
+            return false;
         }
 
-        // Castle right (king side)
-        if self.right_castling_rights[piece.color().zobrist_index()] {
-            if let (Some(king_piece), Some(rook_piece)) = (self.piece_at(Vector(4, y)), self.piece_at(Vector(7, y))) {
-                let our_king = king_piece.piece_type() == King && king_piece.color() == piece.color();
-                let our_rook = rook_piece.piece_type() == Rook && rook_piece.color() == piece.color();
-                let empty_between = self.piece_at(Vector(5, y)).is_none() && self.piece_at(Vector(6, y)).is_none(); // TODO: no checks on these squares
-                if our_king && our_rook && empty_between {
-                    moves.push(Move { src: coord, dst: Vector(6, y), kind: MoveKind::KingCastle });
-                }
-            }
+        board.piece_at(self.dst()).unwrap().is_king() // This move is marked as capture, there should be a piece at dst
+    }
+
+    #[inline]
+    pub fn is_capture_with_target(&self) -> bool {
+        self.kind().is_capture_with_target()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::vector::{FILE_E, RANK_1};
+    use crate::chess::zobrist::ZobristTable;
+
+    /// White king and rooks on their start squares, black king on e8, plus the given extra pieces
+    fn castling_board(extra_pieces: &[(Vector, Piece)]) -> Board {
+        let mut board = Board::empty();
+        board.left_castling_rights = [true; 2];
+        board.right_castling_rights = [true; 2];
+
+        board.set_piece(Vector(4, 7), Some(Piece::new(White, King)));
+        board.set_piece(Vector(0, 7), Some(Piece::new(White, Rook)));
+        board.set_piece(Vector(7, 7), Some(Piece::new(White, Rook)));
+        board.set_piece(Vector(4, 0), Some(Piece::new(Black, King)));
+
+        for (coord, piece) in extra_pieces {
+            board.set_piece(*coord, Some(*piece));
         }
 
-        moves
+        board
     }
 
-    #[inline]
-    fn generate_moves_to_squares<I>(&self, coord: Vector, piece: Piece, dsts: I) -> Vec<Move> where I: IntoIterator<Item = Vector> {
-        let mut moves = vec![];
+    fn castling_kinds(board: &Board) -> Vec<MoveKind> {
+        board.generate_legal_moves(White).into_iter()
+            .map(|r#move| r#move.kind())
+            .filter(|kind| *kind == MoveKind::KingCastle || *kind == MoveKind::QueenCastle)
+            .collect()
+    }
 
-        for dst in dsts { // TODO: this is shared with the king: can I go there?
-            if !dst.is_on_board() {
-                continue;
-            }
+    fn legal_destinations(fen: &str, src: &str) -> Vec<String> {
+        let board = Board::from_fen(fen).unwrap();
+        board.generate_legal_piece_moves(src.parse().unwrap()).iter().map(|r#move| r#move.dst().to_string()).collect()
+    }
 
-            if let Some(victim) = self.piece_at(dst) {
-                if victim.color() != piece.color() {
-                    moves.push(Move { src: coord, dst, kind: MoveKind::Capture });
-                }
-            } else {
-                moves.push(Move { src: coord, dst, kind: MoveKind::Quiet });
-            }
-        }
+    #[test]
+    fn pinned_piece_moves_along_pin_line() {
+        // The rook on e4 is pinned by the rook on e8, the knight on d2 by the bishop on a5
+        let fen = "k3r3/8/8/b7/4R3/8/3N4/4K3 w - - 0 1";
+        assert_eq!(legal_destinations(fen, "e4"), vec!["e8", "e7", "e6", "e5", "e3", "e2"]);
+        assert!(legal_destinations(fen, "d2").is_empty());
+    }
 
-        moves
+    #[test]
+    fn only_king_moves_in_double_check() {
+        // Rook on e8 and knight on d3 both give check, the queen could capture the knight but not block the rook
+        let board = Board::from_fen("k3r3/8/8/8/8/3n4/8/3QK3 w - - 0 1").unwrap();
+        let moves = board.generate_legal_moves(White);
+
+        assert!(!moves.is_empty());
+        assert!(moves.iter().all(|r#move| r#move.src() == Vector(FILE_E, RANK_1)));
     }
 
-    #[inline]
-    fn generate_continous_moves_from_offsets<I>(&self, coord: Vector, piece: Piece, offsets: I) -> Vec<Move> where I: IntoIterator<Item = Vector> {
-        let mut moves = vec![];
-
-        for offset in offsets {
-            let mut dst = coord + offset;
-            while dst.is_on_board() {
-                if let Some(victim) = self.piece_at(dst) {
-                    if victim.color() != piece.color() {
-                        moves.push(Move { src: coord, dst: dst, kind: MoveKind::Capture });
-                    }
-                    break; // can't go further
-                } else {
-                    moves.push(Move { src: coord, dst: dst, kind: MoveKind::Quiet });
-                }
-                dst = dst + offset;
-            }
-        }
+    #[test]
+    fn en_passant_exposing_king_along_rank_is_illegal() {
+        // Capturing en passant removes both pawns between the king on a5 and the rook on h5
+        let fen = "8/8/8/KPp4r/8/8/8/7k w - c6 0 1";
+        assert_eq!(legal_destinations(fen, "b5"), vec!["b6"]);
+    }
 
-        moves
+    #[test]
+    fn king_cannot_retreat_along_checking_ray() {
+        // f4 is shadowed by the king itself, d4 is attacked directly
+        let fen = "k7/8/8/8/r3K3/8/8/8 w - - 0 1";
+        let mut destinations = legal_destinations(fen, "e4");
+        destinations.sort();
+        assert_eq!(destinations, vec!["d3", "d5", "e3", "e5", "f3", "f5"]);
     }
-}
 
-impl Move {
-    #[inline]
-    pub fn is_capture_king(&self, board: &Board) -> bool {
-        if !self.is_capture_with_target() {
-            return false;
+    #[test]
+    fn castling_allowed_on_both_sides() {
+        let board = castling_board(&[]);
+        assert_eq!(castling_kinds(&board), vec![MoveKind::QueenCastle, MoveKind::KingCastle]);
+    }
+
+    #[test]
+    fn no_castling_out_of_check() {
+        let board = castling_board(&[(Vector(4, 3), Piece::new(Black, Rook))]);
+        assert!(castling_kinds(&board).is_empty());
+    }
+
+    #[test]
+    fn no_castling_through_check() {
+        let board = castling_board(&[(Vector(5, 2), Piece::new(Black, Rook))]);
+        assert_eq!(castling_kinds(&board), vec![MoveKind::QueenCastle]);
+
+        let board = castling_board(&[(Vector(3, 2), Piece::new(Black, Rook))]);
+        assert_eq!(castling_kinds(&board), vec![MoveKind::KingCastle]);
+    }
+
+    #[test]
+    fn no_castling_into_check() {
+        let board = castling_board(&[(Vector(6, 2), Piece::new(Black, Rook))]);
+        assert_eq!(castling_kinds(&board), vec![MoveKind::QueenCastle]);
+
+        let board = castling_board(&[(Vector(2, 2), Piece::new(Black, Rook))]);
+        assert_eq!(castling_kinds(&board), vec![MoveKind::KingCastle]);
+    }
+
+    #[test]
+    fn queen_side_castling_allowed_with_attacked_rook_path() {
+        // Only the rook passes b1, the king does not
+        let board = castling_board(&[(Vector(1, 2), Piece::new(Black, Rook))]);
+        assert_eq!(castling_kinds(&board), vec![MoveKind::QueenCastle, MoveKind::KingCastle]);
+    }
+
+    #[test]
+    fn chess960_castling() {
+        let zobrist_table = ZobristTable::default();
+
+        // The king stays on g1, or the rook starts on the king's destination
+        for (fen, castled) in [
+            ("4k3/8/8/8/8/8/8/6KR w H - 0 1", "4k3/8/8/8/8/8/8/5RK1 b - - 1 1"),
+            ("4k3/8/8/8/8/8/8/4K1R1 w G - 0 1", "4k3/8/8/8/8/8/8/5RK1 b - - 1 1"),
+            ("1r2k3/8/8/8/8/8/8/1RK5 w B - 0 1", "1r2k3/8/8/8/8/8/8/2KR4 b - - 1 1"),
+        ] {
+            let mut board = Board::from_fen(fen).unwrap();
+            let original = board.clone();
+            let castling = board.generate_legal_moves(White).into_iter()
+                .find(|r#move| matches!(r#move.kind(), MoveKind::KingCastle | MoveKind::QueenCastle))
+                .expect(fen);
+
+            let undo = board.make_move(castling, &zobrist_table);
+            assert_eq!(board.to_fen(), castled);
+            board.unmake_move(undo);
+            assert_eq!(board, original);
         }
 
-        board.piece_at(self.dst).unwrap().is_king() // This move is marked as capture, there should be a piece at dst
+        // The castling rook shields the king's destination from the rook on a1
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/rRK5 w B - 0 1").unwrap();
+        assert!(castling_kinds(&board).is_empty());
     }
 
-    #[inline]
-    pub fn is_capture_with_target(&self) -> bool {
-        self.kind.is_capture_with_target()
+    #[test]
+    fn capturing_rook_revokes_castling_rights() {
+        let zobrist_table = ZobristTable::default();
+        let mut board = Board::from_fen("4k3/7r/8/8/8/8/8/R3K2R b KQ - 0 1").unwrap();
+
+        board.execute_move(Move::new(Vector(7, 1), Vector(7, 7), MoveKind::Capture), &zobrist_table);
+
+        assert!(!board.right_castling_rights[White.zobrist_index()]);
+        assert!(board.left_castling_rights[White.zobrist_index()]);
+    }
+
+    #[test]
+    fn attackers_of_square() {
+        // e3 is attacked by the black rook on e7 and the black knight on f5, the white rook on a3 is blocked by the pawn on c3
+        let board = castling_board(&[
+            (Vector(4, 1), Piece::new(Black, Rook)),
+            (Vector(5, 3), Piece::new(Black, Knight)),
+            (Vector(0, 5), Piece::new(White, Rook)),
+            (Vector(2, 5), Piece::new(Black, Pawn)),
+        ]);
+
+        let attackers: Vec<Vector> = board.attackers_of(Vector(4, 5), Black).collect();
+        assert_eq!(attackers, vec![Vector(4, 1), Vector(5, 3)]);
+        assert!(!board.is_square_attacked(Vector(4, 5), White));
+        assert!(board.is_in_check(White));
+        assert!(!board.is_in_check(Black));
+    }
+
+    #[test]
+    fn packed_move_round_trip() {
+        assert_eq!(std::mem::size_of::<Move>(), 2);
+
+        for kind in MoveKind::ALL {
+            let r#move = Move::new(Vector(7, 0), Vector(0, 7), kind);
+            assert_eq!((r#move.src(), r#move.dst(), r#move.kind()), (Vector(7, 0), Vector(0, 7), kind));
+        }
     }
-}
\ No newline at end of file
+}
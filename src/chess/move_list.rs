@@ -0,0 +1,130 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::chess::r#move::{Move, MoveKind};
+use crate::chess::vector::Vector;
+
+/// No position has more than 218 legal moves, pseudo legal moves stay below this as well
+pub const MAX_MOVES: usize = 256;
+
+const PLACEHOLDER_MOVE: Move = Move::new(Vector(0, 0), Vector(0, 0), MoveKind::Quiet);
+
+/// Fixed capacity move list living on the stack, so move generation doesn't need to allocate at every node
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> Self {
+        Self { moves: [PLACEHOLDER_MOVE; MAX_MOVES], len: 0 }
+    }
+
+    /// Adds a move, the list has to have room for it. Move generation fills one list per color with pseudo legal moves,
+    /// also for the side not to move as in `evaluate_position`, which stays below `MAX_MOVES` for positions with the usual material.
+    #[inline]
+    pub fn push(&mut self, r#move: Move) {
+        debug_assert!(self.len < MAX_MOVES, "move list is full with {MAX_MOVES} moves");
+        self.moves[self.len] = r#move;
+        self.len += 1;
+    }
+
+    /// Keeps only the moves matching the predicate, preserving their order
+    pub fn retain(&mut self, mut predicate: impl FnMut(&Move) -> bool) {
+        let mut kept = 0;
+        for index in 0..self.len {
+            if predicate(&self.moves[index]) {
+                self.moves[kept] = self.moves[index];
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.moves[..self.len]
+    }
+}
+
+impl DerefMut for MoveList {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl std::fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl IntoIterator for MoveList {
+    type Item = Move;
+    type IntoIter = MoveListIntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        MoveListIntoIter { list: self, index: 0 }
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct MoveListIntoIter {
+    list: MoveList,
+    index: usize,
+}
+
+impl Iterator for MoveListIntoIter {
+    type Item = Move;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let r#move = self.list.get(self.index).copied();
+        self.index += 1;
+        r#move
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.list.len().saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for MoveListIntoIter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_retain_and_iterate() {
+        let mut moves = MoveList::new();
+        for x in 0..8 {
+            moves.push(Move::new(Vector(x, 6), Vector(x, 5), MoveKind::Quiet));
+        }
+        assert_eq!(moves.len(), 8);
+
+        moves.retain(|r#move| r#move.src().0 % 2 == 0);
+        assert_eq!(moves.iter().map(|r#move| r#move.src().0).collect::<Vec<_>>(), vec![0, 2, 4, 6]);
+        assert_eq!(moves.into_iter().map(|r#move| r#move.dst().0).collect::<Vec<_>>(), vec![0, 2, 4, 6]);
+    }
+}
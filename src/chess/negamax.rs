@@ -1,34 +1,79 @@
+use std::time::Instant;
+
 use crate::chess::board::Board;
+use crate::chess::game_status::GameStatus;
 use crate::chess::r#move::Move;
 use crate::chess::transposition::{EntryType, TranspositionTable};
 use crate::chess::zobrist::ZobristTable;
 
 use rayon::prelude::*;
 
+const MATE_SCORE: f64 = 200.0;
+/// Nodes between two reads of the clock, which is slow compared to visiting a node
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
 #[derive(Default, Clone)]
 pub struct OptimizationContext {
     pub transposition_table: TranspositionTable,
+    pub deadline: Option<Instant>,
+    nodes: u64,
+    aborted: bool, // the deadline passed, the scores of the unfinished search are meaningless
+}
+
+impl OptimizationContext {
+    fn is_out_of_time(&mut self) -> bool {
+        if let Some(deadline) = self.deadline {
+            self.nodes += 1;
+            if self.nodes % DEADLINE_CHECK_INTERVAL == 1 && Instant::now() >= deadline {
+                self.aborted = true;
+            }
+        }
+
+        self.aborted
+    }
 }
 
 pub fn negamax_move(board: Board, depth: u8, zobrist_table: &ZobristTable) -> Option<(Move, f64)> {
+    search_root(board, depth, zobrist_table, None)
+}
+
+/// Like `negamax_move`, but gives up once the deadline passes. Returns `None` then, as well as without legal moves.
+pub fn negamax_move_until(board: Board, depth: u8, zobrist_table: &ZobristTable, deadline: Instant) -> Option<(Move, f64)> {
+    search_root(board, depth, zobrist_table, Some(deadline))
+}
+
+fn search_root(board: Board, depth: u8, zobrist_table: &ZobristTable, deadline: Option<Instant>) -> Option<(Move, f64)> {
     // TODO: shared doesn't seem to help
 
-    let moves = board.generate_moves(board.next_player);
+    let moves = board.generate_legal_moves(board.next_player);
 
-    let scores_by_move: Vec<(Move, f64)> = moves.into_par_iter().map(|r#move| {
-        let mut ctx = OptimizationContext::default(); // TODO: trans_table could be shared per thread, zobrist could be shared with everyone
+    let scores_by_move: Vec<(Move, f64)> = moves.par_iter().map(|&r#move| {
+        let mut ctx = OptimizationContext { deadline, ..Default::default() }; // TODO: trans_table could be shared per thread, zobrist could be shared with everyone
 
-        let mut updated_board = board.clone();
-        updated_board.execute_move(r#move, zobrist_table);
+        let mut updated_board = board.clone(); // one copy per thread, the search below makes and unmakes moves on it
+        updated_board.make_move(r#move, zobrist_table);
 
-        (r#move, -negamax(updated_board, depth - 1, f64::NEG_INFINITY, f64::INFINITY, zobrist_table, &mut ctx)) // clone context for parallel or use Arc<Mutex<_>>
-    }).collect();
+        let score = -negamax(&mut updated_board, depth - 1, f64::NEG_INFINITY, f64::INFINITY, zobrist_table, &mut ctx); // clone context for parallel or use Arc<Mutex<_>>
+        (!ctx.aborted).then_some((r#move, score))
+    }).collect::<Option<_>>()?;
 
     // TODO: if scores are equal, do eval and take the earlier, better move
     scores_by_move.into_iter().max_by(|l, r| l.1.partial_cmp(&r.1).unwrap())
 }
 
-fn negamax(board: Board, depth: u8, mut alpha: f64, beta: f64, zobrist_table: &ZobristTable, ctx: &mut OptimizationContext) -> f64 {
+fn negamax(board: &mut Board, depth: u8, mut alpha: f64, beta: f64, zobrist_table: &ZobristTable, ctx: &mut OptimizationContext) -> f64 {
+    // Unwinds the search without touching the transposition table, the caller discards the result
+    if ctx.is_out_of_time() {
+        return 0.0;
+    }
+
+    // Draws by repetition or the fifty-move rule depend on the path, so they are checked before the transposition table.
+    // A mate given with the move that reached the draw still takes precedence, as in `game_status`.
+    if board.is_repetition() || board.is_fifty_move_rule() {
+        let is_checkmate = board.is_in_check(board.next_player) && board.generate_legal_moves(board.next_player).is_empty();
+        return if is_checkmate { -MATE_SCORE - depth as f64 } else { 0.0 };
+    }
+
     // Lookup transposition table
     if let Some((score, entry_type)) = ctx.transposition_table.lookup(board.zobrist_hash, depth) {
         match entry_type {
@@ -48,6 +93,16 @@ fn negamax(board: Board, depth: u8, mut alpha: f64, beta: f64, zobrist_table: &Z
         }
     }
 
+    // Checked before the static evaluation, so mates and stalemates at the horizon are scored as such
+    let next_player = board.next_player;
+    let mut moves = board.generate_legal_moves(next_player);
+
+    match board.game_status_with_moves(&moves) {
+        GameStatus::Checkmate(_) => return -MATE_SCORE - depth as f64, // prefer quicker mates
+        GameStatus::Stalemate | GameStatus::Draw(_) => return 0.0,
+        GameStatus::Ongoing => {}
+    }
+
     if depth == 0 {
         let score = board.evaluate_position_for_current_player();
 
@@ -56,36 +111,25 @@ fn negamax(board: Board, depth: u8, mut alpha: f64, beta: f64, zobrist_table: &Z
         return score;
     }
 
-    let next_player = board.next_player;
-    let mut moves = board.generate_moves(next_player);
-
     // Move ordering
-    moves.sort_by_key(|r#move| r#move.order_score(&board));
+    moves.sort_by_key(|r#move| r#move.order_score(board));
 
     let orig_alpha = alpha;
 
-    let scores = moves.into_iter().map(|r#move| {
-        if alpha >= beta {
-            return f64::NEG_INFINITY; // TODO: for loop -> abort
-        }
-
-        // King capture is terminal move
-        if r#move.is_capture_king(&board) {
-            return 200.0;
-        }
-
-        let mut updated_board = board.clone(); // TODO: get mutable board, make move, unmake move
-        updated_board.execute_move(r#move, zobrist_table);
-
-        let score = -negamax(updated_board, depth - 1, -beta, -alpha, zobrist_table, ctx);
-
-        alpha = alpha.max(score);
+    // TODO: if scores are equal, do eval and take the earlier, better move
+    let mut score = f64::NEG_INFINITY; // game is ongoing, there is at least one move
+    for r#move in moves {
+        let undo = board.make_move(r#move, zobrist_table);
+        let move_score = -negamax(board, depth - 1, -beta, -alpha, zobrist_table, ctx);
+        board.unmake_move(undo);
 
-        score
-    });
+        score = score.max(move_score);
+        alpha = alpha.max(move_score);
 
-    // TODO: if scores are equal, do eval and take the earlier, better move
-    let score = scores.max_by(|l, r| l.partial_cmp(&r).unwrap()).unwrap_or(0.0); // draw evaluates to 0, but this would ignore king taken!!! -> abort early / take into account
+        if alpha >= beta {
+            break;
+        }
+    }
 
     if score <= orig_alpha {
         ctx.transposition_table.insert(board.zobrist_hash, depth, score, EntryType::UpperBound);
@@ -101,4 +145,52 @@ fn negamax(board: Board, depth: u8, mut alpha: f64, beta: f64, zobrist_table: &Z
 
 
 
-// TODO: extract to generic?
\ No newline at end of file
+// TODO: extract to generic?
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_mate_at_horizon() {
+        let zobrist_table = ZobristTable::default();
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+
+        let (r#move, score) = negamax_move(board, 1, &zobrist_table).unwrap();
+        assert_eq!(r#move.to_uci(), "a1a8");
+        assert!(score >= MATE_SCORE);
+    }
+
+    #[test]
+    fn mate_takes_precedence_over_fifty_move_rule() {
+        let zobrist_table = ZobristTable::default();
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80").unwrap();
+
+        let (r#move, score) = negamax_move(board, 2, &zobrist_table).unwrap();
+        assert_eq!(r#move.to_uci(), "a1a8");
+        assert!(score >= MATE_SCORE);
+    }
+
+    #[test]
+    fn deadline_aborts_search() {
+        let zobrist_table = ZobristTable::default();
+        let far_future = Instant::now() + std::time::Duration::from_secs(3600);
+
+        assert_eq!(negamax_move_until(Board::default(), 3, &zobrist_table, Instant::now()), None);
+        assert_eq!(
+            negamax_move_until(Board::default(), 2, &zobrist_table, far_future),
+            negamax_move(Board::default(), 2, &zobrist_table),
+        );
+    }
+
+    #[test]
+    fn stalemate_at_horizon_is_a_draw() {
+        let zobrist_table = ZobristTable::default();
+
+        // Qg6 stalemates, any other queen move keeps the win on the board
+        let mut board = Board::from_fen("7k/8/8/8/8/8/6Q1/K7 w - - 0 1").unwrap();
+        let stalemating = board.parse_uci_move("g2g6").unwrap();
+        board.make_move(stalemating, &zobrist_table);
+        assert_eq!(negamax(&mut board, 0, f64::NEG_INFINITY, f64::INFINITY, &zobrist_table, &mut OptimizationContext::default()), 0.0);
+    }
+}
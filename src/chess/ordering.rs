@@ -1,4 +1,4 @@
-use egui::emath::OrderedFloat;
+use ordered_float::OrderedFloat;
 use crate::chess::board::Board;
 use crate::chess::r#move::Move;
 
@@ -7,9 +7,9 @@ impl Move {
     pub fn order_score(&self, board: &Board) -> OrderedFloat<f64> {
         // last moved piece, least valuable attacker
 
-        if let Some(Move { dst, .. }) = board.last_move {
-            if dst == self.dst {
-                return OrderedFloat(board.piece_at(self.src).unwrap().piece_value()); // unwrap - has to be valid moves
+        if let Some(last_move) = board.last_move {
+            if last_move.dst() == self.dst() {
+                return OrderedFloat(board.piece_at(self.src()).unwrap().piece_value()); // unwrap - has to be valid moves
             }
 
             OrderedFloat(f64::INFINITY)
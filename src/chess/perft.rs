@@ -0,0 +1,138 @@
+use std::sync::Mutex;
+
+use hashbrown::HashMap;
+use rayon::prelude::*;
+
+use crate::chess::board::Board;
+use crate::chess::r#move::Move;
+use crate::chess::zobrist::ZobristTable;
+
+/// Number of independently locked parts of the perft cache, so threads rarely wait for each other
+const CACHE_SHARDS: usize = 64;
+
+/// Subtree leaf counts by zobrist hash and remaining depth, shared by all threads of a parallel perft.
+/// The shards are chosen by hash, so transpositions between different root moves are found as well.
+struct PerftCache {
+    shards: Vec<Mutex<HashMap<(u64, u8), u64>>>,
+}
+
+impl PerftCache {
+    fn new() -> Self {
+        Self { shards: (0..CACHE_SHARDS).map(|_| Mutex::new(HashMap::new())).collect() }
+    }
+
+    fn shard(&self, hash: u64) -> &Mutex<HashMap<(u64, u8), u64>> {
+        &self.shards[hash as usize % CACHE_SHARDS]
+    }
+
+    fn get(&self, hash: u64, depth: u8) -> Option<u64> {
+        self.shard(hash).lock().expect("expected no panic while holding a perft cache lock").get(&(hash, depth)).copied()
+    }
+
+    fn insert(&self, hash: u64, depth: u8, nodes: u64) {
+        self.shard(hash).lock().expect("expected no panic while holding a perft cache lock").insert((hash, depth), nodes);
+    }
+}
+
+impl Board {
+    /// Counts the leaf nodes of the legal move tree of the given depth, used to validate move generation against known counts
+    pub fn perft(&self, depth: u8, zobrist_table: &ZobristTable) -> u64 {
+        let mut board = self.clone(); // the traversal makes and unmakes moves on its own copy
+        board.count_leaves(depth, zobrist_table)
+    }
+
+    /// Perft split by root move, to narrow down which move a wrong count comes from
+    pub fn divide(&self, depth: u8, zobrist_table: &ZobristTable) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return vec![];
+        }
+
+        let mut board = self.clone();
+        board.generate_legal_moves(board.next_player).into_iter()
+            .map(|r#move| {
+                let undo = board.make_move(r#move, zobrist_table);
+                let nodes = board.count_leaves(depth - 1, zobrist_table);
+                board.unmake_move(undo);
+
+                (r#move, nodes)
+            })
+            .collect()
+    }
+
+    /// Perft with the root moves split over the rayon pool, see `divide_parallel`
+    pub fn perft_parallel(&self, depth: u8, zobrist_table: &ZobristTable, use_cache: bool) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        self.divide_parallel(depth, zobrist_table, use_cache).iter().map(|(_, nodes)| nodes).sum()
+    }
+
+    /// Divide with every root move counted on its own thread.
+    /// With `use_cache`, subtree counts of transposed positions are reused, also between the subtrees of different root moves.
+    pub fn divide_parallel(&self, depth: u8, zobrist_table: &ZobristTable, use_cache: bool) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return vec![];
+        }
+
+        let moves = self.generate_legal_moves(self.next_player);
+        let cache = PerftCache::new();
+
+        moves.par_iter().map(|&r#move| {
+            let mut board = self.clone(); // one copy per thread
+            board.make_move(r#move, zobrist_table);
+
+            let nodes = if use_cache {
+                board.count_leaves_cached(depth - 1, zobrist_table, &cache)
+            } else {
+                board.count_leaves(depth - 1, zobrist_table)
+            };
+
+            (r#move, nodes)
+        }).collect()
+    }
+
+    fn count_leaves(&mut self, depth: u8, zobrist_table: &ZobristTable) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.generate_legal_moves(self.next_player);
+
+        // Bulk counting, the moves of the last ply don't need to be made
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        let mut nodes = 0;
+        for r#move in moves {
+            let undo = self.make_move(r#move, zobrist_table);
+            nodes += self.count_leaves(depth - 1, zobrist_table);
+            self.unmake_move(undo);
+        }
+
+        nodes
+    }
+
+    fn count_leaves_cached(&mut self, depth: u8, zobrist_table: &ZobristTable, cache: &PerftCache) -> u64 {
+        // Bulk counted nodes are cheaper to count than to cache
+        if depth <= 1 {
+            return self.count_leaves(depth, zobrist_table);
+        }
+
+        if let Some(nodes) = cache.get(self.zobrist_hash, depth) {
+            return nodes;
+        }
+
+        let mut nodes = 0;
+        for r#move in self.generate_legal_moves(self.next_player) {
+            let undo = self.make_move(r#move, zobrist_table);
+            nodes += self.count_leaves_cached(depth - 1, zobrist_table, cache);
+            self.unmake_move(undo);
+        }
+
+        cache.insert(self.zobrist_hash, depth, nodes);
+
+        nodes
+    }
+}
@@ -0,0 +1,543 @@
+use std::fmt::Display;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use itertools::Itertools;
+
+use crate::chess::board::{Board, Color};
+use crate::chess::fen::{FenError, START_POSITION_FEN};
+use crate::chess::game::{Annotation, Game, GameMove, Variation};
+use crate::chess::game_status::GameResult;
+use crate::chess::san::SanError;
+use crate::chess::zobrist::ZobristTable;
+
+/// Tags every PGN game has, written first and in this order
+pub const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+const MAX_LINE_LENGTH: usize = 80;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PgnError {
+    UnterminatedTag,
+    UnterminatedComment,
+    InvalidTag(String),
+    UnbalancedVariation,
+    VariationWithoutMove,
+    InvalidFen(FenError),
+    IllegalMove { ply: usize, san: String, error: SanError },
+}
+
+impl Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PgnError::UnterminatedTag => write!(f, "tag pair is missing its closing ']'"),
+            PgnError::UnterminatedComment => write!(f, "comment is missing its closing '}}'"),
+            PgnError::InvalidTag(tag) => write!(f, "invalid tag pair '[{tag}]'"),
+            PgnError::UnbalancedVariation => write!(f, "unbalanced parentheses around variations"),
+            PgnError::VariationWithoutMove => write!(f, "variation before the first move of its line"),
+            PgnError::InvalidFen(error) => write!(f, "invalid FEN tag: {error}"),
+            PgnError::IllegalMove { ply, san, error } => write!(f, "move '{san}' at ply {ply}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<FenError> for PgnError {
+    fn from(error: FenError) -> Self {
+        PgnError::InvalidFen(error)
+    }
+}
+
+impl Game {
+    /// Writes all moves of the game, regardless of the current ply, with the seven tag roster first.
+    /// The result tag always matches the game's result.
+    pub fn to_pgn(&self, zobrist_table: &ZobristTable) -> String {
+        let mut pgn = String::new();
+
+        let start_fen = self.start_position().to_fen();
+        let is_custom_start = start_fen != START_POSITION_FEN;
+
+        for name in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => self.result.to_string(),
+                "Date" => self.tag(name).unwrap_or("????.??.??").to_owned(),
+                _ => self.tag(name).unwrap_or("?").to_owned(),
+            };
+            write_tag(&mut pgn, name, &value);
+        }
+        for (name, value) in &self.tags {
+            if !SEVEN_TAG_ROSTER.contains(&name.as_str()) && name != "SetUp" && name != "FEN" {
+                write_tag(&mut pgn, name, value);
+            }
+        }
+        if is_custom_start {
+            write_tag(&mut pgn, "SetUp", "1");
+            write_tag(&mut pgn, "FEN", &start_fen);
+        }
+        pgn.push('\n');
+
+        // Move text, wrapped to keep lines short
+        let mut tokens = vec![];
+        write_line(&mut tokens, self.start_position().clone(), &self.comments, self.moves(), zobrist_table);
+        tokens.push(self.result.to_string());
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+            pgn.push_str(&token);
+            line_length += token.len();
+        }
+        pgn.push('\n');
+
+        pgn
+    }
+}
+
+fn write_tag(pgn: &mut String, name: &str, value: &str) {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    pgn.push_str(&format!("[{name} \"{escaped}\"]\n"));
+}
+
+/// Appends the move text tokens of a line starting at the given position, with its comments, NAGs and nested variations
+fn write_line(tokens: &mut Vec<String>, mut board: Board, comments: &[String], moves: &[GameMove], zobrist_table: &ZobristTable) {
+    write_comments(tokens, comments);
+
+    // Black's moves need a number at the start of a line and after commentary interrupted it
+    let mut needs_move_number = true;
+    for game_move in moves {
+        if board.next_player == Color::White {
+            tokens.push(format!("{}.", board.fullmove_number));
+        } else if needs_move_number {
+            tokens.push(format!("{}...", board.fullmove_number));
+        }
+        tokens.push(board.move_to_san(game_move.r#move, zobrist_table));
+
+        let annotation = &game_move.annotation;
+        tokens.extend(annotation.nags.iter().map(|nag| format!("${nag}")));
+        write_comments(tokens, &annotation.comments);
+        for variation in &annotation.variations {
+            let mut variation_tokens = vec![];
+            write_line(&mut variation_tokens, board.clone(), &variation.comments, &variation.moves, zobrist_table);
+            push_enclosed(tokens, variation_tokens, '(', ')');
+        }
+        needs_move_number = !annotation.comments.is_empty() || !annotation.variations.is_empty();
+
+        board.execute_move(game_move.r#move, zobrist_table);
+    }
+}
+
+/// Comments are split into words, so they are wrapped like the moves
+fn write_comments(tokens: &mut Vec<String>, comments: &[String]) {
+    for comment in comments {
+        push_enclosed(tokens, comment.split_whitespace().map(str::to_owned).collect(), '{', '}');
+    }
+}
+
+/// Appends the tokens with the delimiters attached to the first and last of them, like "(2." and "Bb5)"
+fn push_enclosed(tokens: &mut Vec<String>, mut enclosed: Vec<String>, open: char, close: char) {
+    let Some(last) = enclosed.last_mut() else {
+        tokens.push(format!("{open}{close}"));
+        return;
+    };
+
+    last.push(close);
+    enclosed[0].insert(0, open);
+    tokens.extend(enclosed);
+}
+
+/// Parses all games of a PGN file. The moves of the main line and of all variations are replayed and validated,
+/// comments, NAGs and variations are kept in the annotation of the move they follow.
+pub fn parse_pgn(text: &str, zobrist_table: &ZobristTable) -> Result<Vec<Game>, PgnError> {
+    let mut games = vec![];
+
+    let mut tags: Vec<(String, String)> = vec![];
+    let mut comments = vec![]; // before the first move, until the game is started
+    let mut game: Option<Game> = None; // once move text started
+    let mut previous_board: Option<Board> = None; // before the last move of the main line, where its variations start
+    let mut variations: Vec<OpenVariation> = vec![]; // innermost last
+
+    for token in tokenize(text)? {
+        match token {
+            Token::Tag(name, value) => {
+                if !variations.is_empty() {
+                    return Err(PgnError::UnbalancedVariation);
+                }
+
+                // Tags after move text without a result start the next game
+                if let Some(finished) = game.take() {
+                    games.push(finished);
+                }
+                comments.clear();
+                tags.push((name, value));
+            }
+            Token::VariationStart => {
+                let (board, ply) = match variations.last() {
+                    Some(parent) if !parent.variation.moves.is_empty() => (parent.previous_board.clone(), parent.ply - 1),
+                    None => match (&game, &previous_board) {
+                        (Some(game), Some(board)) => (board.clone(), game.ply() - 1),
+                        _ => return Err(PgnError::VariationWithoutMove),
+                    },
+                    _ => return Err(PgnError::VariationWithoutMove),
+                };
+                variations.push(OpenVariation { variation: Variation::default(), previous_board: board.clone(), board, ply });
+            }
+            Token::VariationEnd => {
+                let finished = variations.pop().ok_or(PgnError::UnbalancedVariation)?;
+                let annotation = last_annotation(&mut game, &mut variations).expect("expected the move the variation branches off");
+                annotation.variations.push(finished.variation);
+            }
+            Token::Comment(comment) => {
+                let line_comments = match variations.last_mut() {
+                    Some(open) => match open.variation.moves.last_mut() {
+                        Some(game_move) => &mut game_move.annotation.comments,
+                        None => &mut open.variation.comments,
+                    },
+                    None => match game.as_mut() {
+                        Some(game) if game.ply() > 0 => &mut game.annotation_mut().expect("expected a move before the current ply").comments,
+                        Some(game) => &mut game.comments,
+                        None => &mut comments,
+                    },
+                };
+                line_comments.push(comment);
+            }
+            Token::Nag(nag) => {
+                // NAGs before the first move of a line have nothing to annotate
+                if let Some(annotation) = last_annotation(&mut game, &mut variations) {
+                    annotation.nags.push(nag);
+                }
+            }
+            Token::San(san) => match variations.last_mut() {
+                Some(open) => {
+                    let r#move = open.board.parse_san(&san).map_err(|error| PgnError::IllegalMove { ply: open.ply + 1, san, error })?;
+                    open.previous_board = open.board.clone();
+                    open.board.execute_move(r#move, zobrist_table);
+                    open.ply += 1;
+                    open.variation.moves.push(GameMove { r#move, zobrist_hash: open.board.zobrist_hash, annotation: Annotation::default() });
+                }
+                None => {
+                    if game.is_none() {
+                        game = Some(start_game(std::mem::take(&mut tags), std::mem::take(&mut comments))?);
+                    }
+                    let game = game.as_mut().unwrap();
+
+                    let r#move = game.board().parse_san(&san).map_err(|error| PgnError::IllegalMove { ply: game.ply() + 1, san, error })?;
+                    previous_board = Some(game.board().clone());
+                    game.push_move(r#move, zobrist_table).expect("expected parsed SAN move to be legal");
+                }
+            },
+            Token::Result(_) if !variations.is_empty() => {}
+            Token::Result(result) => {
+                let mut finished = match game.take() {
+                    Some(game) => game,
+                    None => start_game(std::mem::take(&mut tags), std::mem::take(&mut comments))?,
+                };
+                finished.result = result;
+                games.push(finished);
+                previous_board = None;
+            }
+        }
+    }
+
+    if !variations.is_empty() {
+        return Err(PgnError::UnbalancedVariation);
+    }
+
+    // Last game without a result
+    if let Some(finished) = game.take() {
+        games.push(finished);
+    } else if !tags.is_empty() {
+        games.push(start_game(tags, comments)?);
+    }
+
+    Ok(games)
+}
+
+/// A variation while its moves are read
+struct OpenVariation {
+    variation: Variation,
+    board: Board, // position after the moves read so far
+    previous_board: Board, // before the last move, where nested variations start
+    ply: usize, // moves from the start of the game to `board`
+}
+
+/// Annotation of the last move of the innermost line being read, if it has any moves
+fn last_annotation<'a>(game: &'a mut Option<Game>, variations: &'a mut [OpenVariation]) -> Option<&'a mut Annotation> {
+    match variations.last_mut() {
+        Some(open) => open.variation.moves.last_mut().map(|game_move| &mut game_move.annotation),
+        None => game.as_mut()?.annotation_mut(),
+    }
+}
+
+/// Sets up a game from its tags, starting from the FEN tag if there is one
+fn start_game(tags: Vec<(String, String)>, comments: Vec<String>) -> Result<Game, PgnError> {
+    let start_position = match tags.iter().find(|(name, _)| name == "FEN") {
+        Some((_, fen)) => Board::from_fen(fen)?,
+        None => Board::default(),
+    };
+
+    let mut game = Game::new(start_position);
+    game.tags = tags;
+    game.comments = comments;
+
+    Ok(game)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Token {
+    Tag(String, String),
+    San(String),
+    Nag(u8),
+    Comment(String),
+    Result(GameResult),
+    VariationStart,
+    VariationEnd,
+}
+
+/// Splits PGN text into tokens, dropping move numbers and escaped lines. Move suffixes like "!?" become NAGs.
+fn tokenize(text: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    let mut at_line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '%' if at_line_start => skip_line(&mut chars),
+            ';' => {
+                let mut comment = String::new();
+                while let Some(c) = chars.next_if(|c| *c != '\n') {
+                    comment.push(c);
+                }
+                tokens.push(Token::Comment(comment.split_whitespace().join(" ")));
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => comment.push(c),
+                        None => return Err(PgnError::UnterminatedComment),
+                    }
+                }
+                tokens.push(Token::Comment(comment.split_whitespace().join(" ")));
+            }
+            '[' => tokens.push(parse_tag(&mut chars)?),
+            '(' => tokens.push(Token::VariationStart),
+            ')' => tokens.push(Token::VariationEnd),
+            '$' => {
+                let mut digits = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                    digits.push(c);
+                }
+                if let Ok(nag) = digits.parse() {
+                    tokens.push(Token::Nag(nag));
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut symbol = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{}()[];$".contains(*c)) {
+                    symbol.push(c);
+                }
+
+                if let Some(result) = GameResult::from_pgn(&symbol) {
+                    tokens.push(Token::Result(result));
+                } else {
+                    let san = strip_move_number(&symbol);
+                    let (san, suffix) = san.split_at(san.trim_end_matches(['!', '?']).len());
+
+                    // Bare continuation dots are skipped, suffixes may also be written apart from the move
+                    if !san.is_empty() && !san.chars().all(|c| c == '.') {
+                        tokens.push(Token::San(san.to_owned()));
+                    }
+                    if let Some(nag) = suffix_nag(suffix) {
+                        tokens.push(Token::Nag(nag));
+                    }
+                }
+            }
+        }
+
+        at_line_start = c == '\n';
+    }
+
+    Ok(tokens)
+}
+
+/// Removes a move number attached to the move, as in "1.e4" or "12...0-0", leaving castling written with zeros intact
+fn strip_move_number(symbol: &str) -> &str {
+    let after_digits = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+    if after_digits.len() < symbol.len() && after_digits.starts_with('.') {
+        after_digits.trim_start_matches('.')
+    } else {
+        symbol
+    }
+}
+
+/// NAG of a move suffix, as defined by the PGN standard
+fn suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+fn skip_line(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| *c != '\n').is_some() {}
+}
+
+/// Parses the remainder of a tag pair like `Event "Casual game"]`, the opening bracket is already consumed
+fn parse_tag(chars: &mut Peekable<Chars>) -> Result<Token, PgnError> {
+    let mut raw = String::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    loop {
+        let c = chars.next().ok_or(PgnError::UnterminatedTag)?;
+        if c == ']' && !in_string {
+            break;
+        }
+
+        if in_string && !escaped && c == '\\' {
+            escaped = true;
+            raw.push(c);
+            continue;
+        }
+        if c == '"' && !escaped {
+            in_string = !in_string;
+        }
+        escaped = false;
+        raw.push(c);
+    }
+
+    let invalid = || PgnError::InvalidTag(raw.clone());
+
+    let (name, quoted_value) = raw.trim().split_once(char::is_whitespace).ok_or_else(invalid)?;
+    let value = quoted_value.trim().strip_prefix('"').and_then(|value| value.strip_suffix('"')).ok_or_else(invalid)?;
+    let value = value.replace("\\\"", "\"").replace("\\\\", "\\");
+
+    Ok(Token::Tag(name.to_owned(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAMES: &str = r#"[Event "Casual \"blitz\" game"]
+[Site "?"]
+[Date "2024.05.01"]
+[Round "1"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+[ECO "C20"]
+
+% escaped line 1. h4
+1.e4 e5 {the open game} 2. Bc4 $1 (2. Nf3 Nc6 (2... d6) 3. Bb5) 2... Nc6 3. Qh5 Nf6?? ; blunder
+4. Qxf7# 1-0
+
+[Event "Second"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30"]
+
+30... Kd7 31. e4 *
+"#;
+
+    #[test]
+    fn parse_games() {
+        let zobrist_table = ZobristTable::default();
+        let games = parse_pgn(GAMES, &zobrist_table).unwrap();
+        assert_eq!(games.len(), 2);
+
+        let scholars_mate = &games[0];
+        assert_eq!(scholars_mate.tag("Event"), Some("Casual \"blitz\" game"));
+        assert_eq!(scholars_mate.tag("ECO"), Some("C20"));
+        assert_eq!(scholars_mate.result, GameResult::WhiteWins);
+        assert_eq!(scholars_mate.moves().iter().map(|game_move| game_move.r#move.to_uci()).collect::<Vec<_>>(), vec![
+            "e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6", "h5f7",
+        ]);
+
+        let moves = scholars_mate.moves();
+        assert_eq!(moves[1].annotation.comments, vec!["the open game"]);
+        assert_eq!(moves[2].annotation.nags, vec![1]);
+        assert_eq!(moves[5].annotation, Annotation { comments: vec!["blunder".to_owned()], nags: vec![4], variations: vec![] });
+
+        let variation = &moves[2].annotation.variations[0];
+        assert_eq!(variation.moves.iter().map(|game_move| game_move.r#move.to_uci()).collect::<Vec<_>>(), vec!["g1f3", "b8c6", "f1b5"]);
+        let nested = &variation.moves[1].annotation.variations[0];
+        assert_eq!(nested.moves.iter().map(|game_move| game_move.r#move.to_uci()).collect::<Vec<_>>(), vec!["d7d6"]);
+
+        let from_fen = &games[1];
+        assert_eq!(from_fen.start_position().to_fen(), "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30");
+        assert_eq!(from_fen.moves().len(), 2);
+        assert_eq!(from_fen.board().to_fen(), "8/3k4/8/8/4P3/8/8/4K3 b - e3 0 31");
+        assert_eq!(from_fen.result, GameResult::Unknown);
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let zobrist_table = ZobristTable::default();
+
+        for game in parse_pgn(GAMES, &zobrist_table).unwrap() {
+            let pgn = game.to_pgn(&zobrist_table);
+            let read_back = parse_pgn(&pgn, &zobrist_table).unwrap();
+
+            assert_eq!(read_back.len(), 1);
+            assert_eq!(read_back[0].moves(), game.moves());
+            assert_eq!(read_back[0].comments, game.comments);
+            assert_eq!(read_back[0].result, game.result);
+            assert_eq!(read_back[0].start_position(), game.start_position());
+        }
+
+        let annotated = parse_pgn(GAMES, &zobrist_table).unwrap()[0].to_pgn(&zobrist_table);
+        assert!(annotated.ends_with(
+            "1. e4 e5 {the open game} 2. Bc4 $1 (2. Nf3 Nc6 (2... d6) 3. Bb5) 2... Nc6 3. Qh5\nNf6 $4 {blunder} 4. Qxf7# 1-0\n"
+        ));
+
+        let mut commented = parse_pgn("{King's pawn} 1. e4 (1. d4 {queen's pawn}) 1... c5 *", &zobrist_table).unwrap().remove(0);
+        assert_eq!(commented.comments, vec!["King's pawn"]);
+        assert_eq!(commented.moves()[0].annotation.variations[0].moves[0].annotation.comments, vec!["queen's pawn"]);
+        assert!(commented.to_pgn(&zobrist_table).ends_with("{King's pawn} 1. e4 (1. d4 {queen's pawn}) 1... c5 *\n"));
+
+        commented.go_to_ply(2, &zobrist_table);
+        commented.annotation_mut().unwrap().nags.push(5);
+        assert!(commented.to_pgn(&zobrist_table).ends_with("1... c5 $5 *\n"));
+
+        let written = parse_pgn(GAMES, &zobrist_table).unwrap()[1].to_pgn(&zobrist_table);
+        assert!(written.starts_with("[Event \"Second\"]\n[Site \"?\"]\n[Date \"????.??.??\"]"));
+        assert!(written.ends_with("[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 30\"]\n\n30... Kd7 31. e4 *\n"));
+    }
+
+    #[test]
+    fn move_numbers_attached_to_castling() {
+        let zobrist_table = ZobristTable::default();
+        let games = parse_pgn("1.e4 e5 2.Nf3 Nc6 3.Bc4 Bc5 4.0-0 d6 5.d3 Be6 6.Nc3 Qd7 7.Be3 Nf6 8.Qd2 8...0-0-0 *", &zobrist_table).unwrap();
+
+        let moves = games[0].moves().iter().map(|game_move| game_move.r#move.to_uci()).collect::<Vec<_>>();
+        assert_eq!(moves.len(), 16);
+        assert_eq!(moves[6], "e1g1");
+        assert_eq!(moves[15], "e8c8");
+    }
+
+    #[test]
+    fn errors() {
+        let zobrist_table = ZobristTable::default();
+
+        assert_eq!(parse_pgn("1. e4 { unterminated", &zobrist_table).unwrap_err(), PgnError::UnterminatedComment);
+        assert_eq!(parse_pgn("1. e4 (1. d4 *", &zobrist_table).unwrap_err(), PgnError::UnbalancedVariation);
+        assert_eq!(parse_pgn("(1. d4) 1. e4 *", &zobrist_table).unwrap_err(), PgnError::VariationWithoutMove);
+        assert!(matches!(parse_pgn("1. e4 (1. e5) *", &zobrist_table), Err(PgnError::IllegalMove { ply: 1, .. })));
+        assert_eq!(parse_pgn("[Event]", &zobrist_table).unwrap_err(), PgnError::InvalidTag("Event".to_owned()));
+        assert!(matches!(parse_pgn("1. e4 e5 2. Ke3 *", &zobrist_table), Err(PgnError::IllegalMove { ply: 3, .. })));
+    }
+}
@@ -0,0 +1,266 @@
+use std::fmt::Display;
+
+use crate::chess::board::{Board, PieceType};
+use crate::chess::game_status::GameStatus;
+use crate::chess::r#move::{Move, MoveKind};
+use crate::chess::vector::Vector;
+use crate::chess::zobrist::ZobristTable;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SanError {
+    Empty,
+    InvalidSyntax(String),
+    NoMatchingMove(String),
+    Ambiguous(String),
+}
+
+impl Display for SanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SanError::Empty => write!(f, "empty move"),
+            SanError::InvalidSyntax(san) => write!(f, "'{san}' is not a move in standard algebraic notation"),
+            SanError::NoMatchingMove(san) => write!(f, "no legal move matches '{san}'"),
+            SanError::Ambiguous(san) => write!(f, "'{san}' matches more than one legal move"),
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+impl Board {
+    /// Standard algebraic notation of a legal move, e.g. "Nbd7", "exd6", "e8=Q", "O-O" or "Qxf7#"
+    pub fn move_to_san(&self, r#move: Move, zobrist_table: &ZobristTable) -> String {
+        let mut san = match r#move.kind() {
+            MoveKind::KingCastle => "O-O".to_owned(),
+            MoveKind::QueenCastle => "O-O-O".to_owned(),
+            _ => self.move_to_san_without_suffix(r#move),
+        };
+
+        let mut after_move = self.clone();
+        after_move.make_move(r#move, zobrist_table);
+        if after_move.is_in_check(after_move.next_player) {
+            san.push(if matches!(after_move.game_status(), GameStatus::Checkmate(_)) { '#' } else { '+' });
+        }
+
+        san
+    }
+
+    fn move_to_san_without_suffix(&self, r#move: Move) -> String {
+        let piece = self.piece_at(r#move.src()).expect("expected move to be valid");
+        let is_capture = r#move.is_capture_with_target() || r#move.kind() == MoveKind::EPCapture;
+
+        let mut san = String::new();
+
+        if piece.is_pawn() {
+            if is_capture {
+                san.push(r#move.src().file_char());
+            }
+        } else {
+            san.push(piece_char(piece.piece_type()));
+
+            // Other pieces of the same type that could move to the destination as well
+            let others = self.generate_legal_moves(self.next_player).into_iter()
+                .filter(|other| other.dst() == r#move.dst() && other.src() != r#move.src())
+                .filter(|other| self.piece_at(other.src()).is_some_and(|other_piece| other_piece.piece_type() == piece.piece_type()))
+                .map(|other| other.src())
+                .collect::<Vec<_>>();
+
+            if !others.is_empty() {
+                if others.iter().all(|other| other.0 != r#move.src().0) {
+                    san.push(r#move.src().file_char());
+                } else if others.iter().all(|other| other.1 != r#move.src().1) {
+                    san.push(r#move.src().rank_char());
+                } else {
+                    san.push_str(&r#move.src().to_string());
+                }
+            }
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&r#move.dst().to_string());
+
+        if let Some(promotion_piece) = r#move.kind().promotion_piece() {
+            san.push('=');
+            san.push(piece_char(promotion_piece));
+        }
+
+        san
+    }
+
+    /// Parses a move in standard algebraic notation. Common variants are accepted as well,
+    /// e.g. castling with zeros, missing or superfluous check marks, "e.p." suffixes and promotions without '='.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let invalid = || SanError::InvalidSyntax(san.to_owned());
+
+        let mut text = san.trim()
+            .trim_end_matches(['+', '#', '!', '?'])
+            .trim_end();
+        text = text.strip_suffix("e.p.").unwrap_or(text).trim_end();
+        if text.is_empty() {
+            return Err(SanError::Empty);
+        }
+
+        let legal_moves = self.generate_legal_moves(self.next_player);
+
+        let castling_kind = match text {
+            "O-O" | "0-0" => Some(MoveKind::KingCastle),
+            "O-O-O" | "0-0-0" => Some(MoveKind::QueenCastle),
+            _ => None,
+        };
+        if let Some(kind) = castling_kind {
+            return legal_moves.into_iter().find(|r#move| r#move.kind() == kind).ok_or(SanError::NoMatchingMove(san.to_owned()));
+        }
+
+        let mut chars: Vec<char> = text.chars().filter(|c| !matches!(c, 'x' | ':' | '-')).collect();
+
+        let piece_type = match chars.first() {
+            Some(&c) if c.is_ascii_uppercase() => {
+                chars.remove(0);
+                piece_type_from_char(c).ok_or_else(invalid)?
+            }
+            _ => PieceType::Pawn,
+        };
+
+        // Promotion piece after the destination, with or without '='
+        let mut promotion_piece = None;
+        if let Some(&last) = chars.last() {
+            if !last.is_ascii_digit() {
+                promotion_piece = Some(piece_type_from_char(last.to_ascii_uppercase()).ok_or_else(invalid)?);
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+            }
+        }
+
+        if chars.len() < 2 {
+            return Err(invalid());
+        }
+        let dst: Vector = chars[chars.len() - 2..].iter().collect::<String>().parse().map_err(|_| invalid())?;
+
+        // Whatever remains disambiguates the source square by file, rank or both
+        let mut src_file = None;
+        let mut src_rank = None;
+        for c in &chars[..chars.len() - 2] {
+            match c {
+                'a'..='h' => src_file = Some(*c as i8 - 'a' as i8),
+                '1'..='8' => src_rank = Some('8' as i8 - *c as i8),
+                _ => return Err(invalid()),
+            }
+        }
+
+        let mut candidates = legal_moves.into_iter().filter(|r#move| {
+            r#move.dst() == dst
+                && r#move.kind().promotion_piece() == promotion_piece
+                && !matches!(r#move.kind(), MoveKind::KingCastle | MoveKind::QueenCastle)
+                && src_file.is_none_or(|file| r#move.src().0 == file)
+                && src_rank.is_none_or(|rank| r#move.src().1 == rank)
+                && self.piece_at(r#move.src()).is_some_and(|piece| piece.piece_type() == piece_type)
+        });
+
+        let r#move = candidates.next().ok_or(SanError::NoMatchingMove(san.to_owned()))?;
+        if candidates.next().is_some() {
+            return Err(SanError::Ambiguous(san.to_owned()));
+        }
+
+        Ok(r#move)
+    }
+}
+
+fn piece_char(piece_type: PieceType) -> char {
+    match piece_type {
+        PieceType::Pawn => 'P',
+        PieceType::Knight => 'N',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        PieceType::Queen => 'Q',
+        PieceType::King => 'K',
+    }
+}
+
+fn piece_type_from_char(c: char) -> Option<PieceType> {
+    match c {
+        'P' => Some(PieceType::Pawn),
+        'N' => Some(PieceType::Knight),
+        'B' => Some(PieceType::Bishop),
+        'R' => Some(PieceType::Rook),
+        'Q' => Some(PieceType::Queen),
+        'K' => Some(PieceType::King),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn san(fen: &str, uci: &str) -> String {
+        let board = Board::from_fen(fen).unwrap();
+        board.move_to_san(board.parse_uci_move(uci).unwrap(), &ZobristTable::default())
+    }
+
+    #[test]
+    fn move_to_san() {
+        assert_eq!(san(KIWIPETE, "e1g1"), "O-O");
+        assert_eq!(san(KIWIPETE, "e1c1"), "O-O-O");
+        assert_eq!(san(KIWIPETE, "e5f7"), "Nxf7");
+        assert_eq!(san(KIWIPETE, "d5e6"), "dxe6");
+        assert_eq!(san(KIWIPETE, "f3f6"), "Qxf6");
+        assert_eq!(san("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1", "a1a8"), "Ra8+");
+        assert_eq!(san(KIWIPETE, "a2a4"), "a4");
+        assert_eq!(san("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", "e5f6"), "exf6");
+        assert_eq!(san("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", "d7c8q"), "dxc8=Q");
+        assert_eq!(san("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2", "d8h4"), "Qh4#");
+    }
+
+    #[test]
+    fn disambiguation() {
+        assert_eq!(san("7k/8/8/8/8/8/8/1N3N1K w - - 0 1", "b1d2"), "Nbd2");
+        assert_eq!(san("7k/8/8/R7/8/8/8/R6K w - - 0 1", "a5a3"), "R5a3");
+        assert_eq!(san("7k/8/8/8/8/8/8/Q2Q3K w - - 0 1", "a1c1"), "Qac1");
+        assert_eq!(san("8/7k/8/8/8/Q7/8/Q1Q4K w - - 0 1", "a1b2"), "Qa1b2");
+    }
+
+    #[test]
+    fn parse_san_variants() {
+        let kiwipete = Board::from_fen(KIWIPETE).unwrap();
+        let parse = |board: &Board, san: &str| board.parse_san(san).map(|r#move| r#move.to_uci());
+
+        assert_eq!(parse(&kiwipete, "0-0"), Ok("e1g1".to_owned()));
+        assert_eq!(parse(&kiwipete, "O-O-O"), Ok("e1c1".to_owned()));
+        assert_eq!(parse(&kiwipete, "Qxf6"), Ok("f3f6".to_owned()));
+        assert_eq!(parse(&kiwipete, "Nf7+!?"), Ok("e5f7".to_owned()));
+
+        let en_passant = Board::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3").unwrap();
+        assert_eq!(parse(&en_passant, "exf6e.p."), Ok("e5f6".to_owned()));
+        assert_eq!(parse(&en_passant, "exf6 e.p."), Ok("e5f6".to_owned()));
+
+        let promotion = Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap();
+        assert_eq!(parse(&promotion, "dxc8=Q"), Ok("d7c8q".to_owned()));
+        assert_eq!(parse(&promotion, "dc8N"), Ok("d7c8n".to_owned()));
+
+        let knights = Board::from_fen("7k/8/8/8/8/8/8/1N3N1K w - - 0 1").unwrap();
+        assert_eq!(parse(&knights, "Nd2"), Err(SanError::Ambiguous("Nd2".to_owned())));
+        assert_eq!(parse(&knights, "Nfd2"), Ok("f1d2".to_owned()));
+        assert_eq!(parse(&knights, "Nd3"), Err(SanError::NoMatchingMove("Nd3".to_owned())));
+        assert_eq!(parse(&knights, "Zd2"), Err(SanError::InvalidSyntax("Zd2".to_owned())));
+        assert_eq!(parse(&knights, "+"), Err(SanError::Empty));
+    }
+
+    #[test]
+    fn round_trip() {
+        let zobrist_table = ZobristTable::default();
+
+        for fen in [KIWIPETE, "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"] {
+            let board = Board::from_fen(fen).unwrap();
+            for r#move in board.generate_legal_moves(board.next_player) {
+                let san = board.move_to_san(r#move, &zobrist_table);
+                assert_eq!(board.parse_san(&san), Ok(r#move), "{san} in {fen}");
+            }
+        }
+    }
+}
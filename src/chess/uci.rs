@@ -0,0 +1,175 @@
+use std::fmt::Display;
+
+use crate::chess::board::{Board, CastlingFiles, PieceType};
+use crate::chess::r#move::{Move, MoveKind};
+use crate::chess::validation::IllegalMove;
+use crate::chess::vector::{InvalidSquare, Vector};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum UciMoveError {
+    InvalidLength(String),
+    InvalidSquare(InvalidSquare),
+    InvalidPromotionPiece(char),
+    Illegal(IllegalMove),
+}
+
+impl Display for UciMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UciMoveError::InvalidLength(s) => write!(f, "invalid move '{s}', expected 4 or 5 characters like 'e2e4' or 'e7e8q'"),
+            UciMoveError::InvalidSquare(error) => write!(f, "{error}"),
+            UciMoveError::InvalidPromotionPiece(c) => write!(f, "invalid promotion piece '{c}', expected one of 'nbrq'"),
+            UciMoveError::Illegal(reason) => write!(f, "illegal move: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for UciMoveError {}
+
+impl From<InvalidSquare> for UciMoveError {
+    fn from(error: InvalidSquare) -> Self {
+        UciMoveError::InvalidSquare(error)
+    }
+}
+
+impl From<IllegalMove> for UciMoveError {
+    fn from(reason: IllegalMove) -> Self {
+        UciMoveError::Illegal(reason)
+    }
+}
+
+impl Move {
+    /// Long algebraic notation as used by UCI, e.g. "e2e4", "e1g1" for castling or "e7e8q" for promotions.
+    /// Chess960 castling needs the position, see `Board::move_to_uci`.
+    pub fn to_uci(&self) -> String {
+        let promotion = match self.kind().promotion_piece() {
+            Some(PieceType::Knight) => "n",
+            Some(PieceType::Bishop) => "b",
+            Some(PieceType::Rook) => "r",
+            Some(PieceType::Queen) => "q",
+            _ => "",
+        };
+
+        format!("{}{}{promotion}", self.src(), self.dst())
+    }
+}
+
+impl Board {
+    /// Long algebraic notation of a move in this position. In Chess960 castling is written as the king moving onto its rook,
+    /// as the king's destination is ambiguous there, e.g. "b1c1" could be a king step and a king on g1 may not move at all.
+    pub fn move_to_uci(&self, r#move: Move) -> String {
+        match self.castling_rook_move(&r#move) {
+            Some((rook_src, _)) if self.castling_files != CastlingFiles::default() => format!("{}{rook_src}", r#move.src()),
+            _ => r#move.to_uci(),
+        }
+    }
+
+    /// Parses a move in long algebraic notation, resolving its kind from the position. The move has to be legal.
+    /// Castling may also be given as the king moving onto its rook, as UCI does for Chess960.
+    pub fn parse_uci_move(&self, uci: &str) -> Result<Move, UciMoveError> {
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return Err(UciMoveError::InvalidLength(uci.to_owned()));
+        }
+
+        let src: Vector = uci[0..2].parse()?;
+        let dst: Vector = uci[2..4].parse()?;
+        let promotion_piece = match uci[4..].chars().next() {
+            None => None,
+            Some('n') => Some(PieceType::Knight),
+            Some('b') => Some(PieceType::Bishop),
+            Some('r') => Some(PieceType::Rook),
+            Some('q') => Some(PieceType::Queen),
+            Some(c) => return Err(UciMoveError::InvalidPromotionPiece(c)),
+        };
+
+        if self.piece_at(src).is_some() {
+            let resolved = self.generate_legal_piece_moves(src).into_iter()
+                .find(|r#move| {
+                    let onto_castling_rook = self.castling_rook_move(r#move).is_some_and(|(rook_src, _)| rook_src == dst);
+                    (r#move.dst() == dst || onto_castling_rook) && r#move.kind().promotion_piece() == promotion_piece
+                });
+
+            if let Some(r#move) = resolved {
+                return Ok(r#move);
+            }
+        }
+
+        // Not legal, validation explains why
+        let kind = match promotion_piece {
+            Some(PieceType::Knight) => MoveKind::PromotionKnight,
+            Some(PieceType::Bishop) => MoveKind::PromotionBishop,
+            Some(PieceType::Rook) => MoveKind::PromotionRook,
+            Some(_) => MoveKind::PromotionQueen,
+            None => MoveKind::Quiet,
+        };
+        self.validate_move(Move::new(src, dst, kind))?;
+
+        unreachable!("validation accepted a move that isn't among the legal moves")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::chess960::CHESS960_POSITION_COUNT;
+    use crate::chess::vector::*;
+    use crate::chess::zobrist::ZobristTable;
+
+    #[test]
+    fn resolves_move_kinds() {
+        let kiwipete = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(kiwipete.parse_uci_move("e1g1").map(|m| m.kind()), Ok(MoveKind::KingCastle));
+        assert_eq!(kiwipete.parse_uci_move("e1c1").map(|m| m.kind()), Ok(MoveKind::QueenCastle));
+        assert_eq!(kiwipete.parse_uci_move("e1h1").map(|m| m.kind()), Ok(MoveKind::KingCastle));
+        assert_eq!(kiwipete.parse_uci_move("a2a4").map(|m| m.kind()), Ok(MoveKind::DoublePawnPush));
+        assert_eq!(kiwipete.parse_uci_move("e5f7").map(|m| m.kind()), Ok(MoveKind::Capture));
+
+        let en_passant = Board::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3").unwrap();
+        assert_eq!(en_passant.parse_uci_move("e5f6"), Ok(Move::new(Vector(FILE_E, RANK_5), Vector(FILE_F, RANK_6), MoveKind::EPCapture)));
+
+        let promotion = Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap();
+        assert_eq!(promotion.parse_uci_move("d7c8n").map(|m| m.kind()), Ok(MoveKind::CapturePromotionKnight));
+        assert_eq!(promotion.parse_uci_move("d7c8"), Err(UciMoveError::Illegal(IllegalMove::MissingPromotionPiece)));
+        assert_eq!(promotion.parse_uci_move("d7d8q"), Err(UciMoveError::Illegal(IllegalMove::Blocked)));
+    }
+
+    #[test]
+    fn round_trip_and_errors() {
+        let board = Board::default();
+        for r#move in board.generate_legal_moves(board.next_player) {
+            assert_eq!(board.parse_uci_move(&r#move.to_uci()), Ok(r#move));
+            assert_eq!(board.move_to_uci(r#move), r#move.to_uci());
+        }
+
+        // Chess960 positions with everything but king and rooks cleared from white's back rank, so castling is possible.
+        // Both sides only where the other rook isn't in the way, e.g. not with rooks on f1 and h1.
+        let zobrist_table = ZobristTable::default();
+        for king_file in [FILE_B, FILE_F, FILE_G] {
+            let index = (0..CHESS960_POSITION_COUNT).find(|index| Board::chess960(*index).castling_files.king == king_file).unwrap();
+            let mut board = Board::chess960(index);
+            for x in FILE_A..=FILE_H {
+                if board.piece_at(Vector(x, RANK_1)).is_some_and(|piece| !piece.is_king() && !piece.is_rook()) {
+                    board.set_piece(Vector(x, RANK_1), None);
+                }
+            }
+            board.zobrist_hash = board.compute_zobrist_hash(&zobrist_table);
+
+            let moves = board.generate_legal_moves(board.next_player);
+            assert!(moves.iter().any(|r#move| board.castling_rook_move(r#move).is_some()), "expected castling in {}", board.to_fen());
+
+            for r#move in moves {
+                let uci = board.move_to_uci(r#move);
+                assert_eq!(board.parse_uci_move(&uci), Ok(r#move), "{uci} in {}", board.to_fen());
+
+                if let Some((rook_src, _)) = board.castling_rook_move(&r#move) {
+                    assert_eq!(uci, format!("{}{rook_src}", Vector(king_file, RANK_1)));
+                }
+            }
+        }
+
+        assert_eq!(board.parse_uci_move("e2e5"), Err(UciMoveError::Illegal(IllegalMove::InvalidMovement)));
+        assert_eq!(board.parse_uci_move("e2"), Err(UciMoveError::InvalidLength("e2".to_owned())));
+        assert_eq!(board.parse_uci_move("e2e9"), Err(UciMoveError::InvalidSquare(InvalidSquare("e9".to_owned()))));
+        assert_eq!(board.parse_uci_move("e2e4k"), Err(UciMoveError::InvalidPromotionPiece('k')));
+    }
+}
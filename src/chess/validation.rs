@@ -0,0 +1,164 @@
+use std::fmt::Display;
+
+use crate::chess::bitboard::{bishop_attacks, pawn_attacks, rook_attacks, square_bit, Bitboard, KING_ATTACKS, KNIGHT_ATTACKS};
+use crate::chess::board::{Board, Color, Piece, PieceType};
+use crate::chess::r#move::{Move, MoveKind};
+use crate::chess::vector::Vector;
+use crate::chess::zobrist::ZobristTable;
+
+/// Why a move can't be executed in a position
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IllegalMove {
+    NoPiece(Vector),
+    WrongSideToMove(Color),
+    /// The piece can't move this way, not even on an empty board
+    InvalidMovement,
+    /// The piece could move this way, but another piece is in the way or occupies the destination
+    Blocked,
+    LeavesKingInCheck,
+    CastlingNotAllowed,
+    MissingPromotionPiece,
+    /// The move exists, but with a different kind, e.g. a capture given as quiet move
+    WrongMoveKind { expected: MoveKind },
+}
+
+impl Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IllegalMove::NoPiece(coord) => write!(f, "there is no piece on {coord}"),
+            IllegalMove::WrongSideToMove(color) => write!(f, "it is not {color:?}'s turn"),
+            IllegalMove::InvalidMovement => write!(f, "the piece can't move like this"),
+            IllegalMove::Blocked => write!(f, "the move is blocked by another piece"),
+            IllegalMove::LeavesKingInCheck => write!(f, "the move would leave the king in check"),
+            IllegalMove::CastlingNotAllowed => write!(f, "castling is not allowed"),
+            IllegalMove::MissingPromotionPiece => write!(f, "a pawn reaching the last rank needs a promotion piece"),
+            IllegalMove::WrongMoveKind { expected } => write!(f, "expected a move of kind {expected:?}"),
+        }
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
+impl Board {
+    /// Executes the move if it is legal in this position, leaving the board untouched otherwise
+    pub fn try_execute_move(&mut self, r#move: Move, zobrist_table: &ZobristTable) -> Result<(), IllegalMove> {
+        self.validate_move(r#move)?;
+        self.execute_move(r#move, zobrist_table);
+        Ok(())
+    }
+
+    pub fn validate_move(&self, r#move: Move) -> Result<(), IllegalMove> {
+        let piece = self.piece_at(r#move.src()).ok_or(IllegalMove::NoPiece(r#move.src()))?;
+        if piece.color() != self.next_player {
+            return Err(IllegalMove::WrongSideToMove(piece.color()));
+        }
+
+        let pseudo_legal_moves = self.generate_piece_moves(r#move.src());
+        let mut same_squares = pseudo_legal_moves.iter().filter(|candidate| candidate.dst() == r#move.dst()).peekable();
+
+        let Some(&first_candidate) = same_squares.peek() else {
+            return Err(self.classify_unreachable(r#move, piece));
+        };
+
+        if !same_squares.any(|candidate| *candidate == r#move) {
+            let promotion_expected = matches!(first_candidate.kind(), MoveKind::PromotionKnight | MoveKind::CapturePromotionKnight);
+            return Err(if promotion_expected {
+                IllegalMove::MissingPromotionPiece
+            } else {
+                IllegalMove::WrongMoveKind { expected: first_candidate.kind() }
+            });
+        }
+
+        if self.leaves_king_in_check(&r#move) {
+            return Err(IllegalMove::LeavesKingInCheck);
+        }
+
+        Ok(())
+    }
+
+    /// Finds the reason the piece can't reach the destination of the move in this position
+    fn classify_unreachable(&self, r#move: Move, piece: Piece) -> IllegalMove {
+        let is_castling_attempt = piece.is_king() && (r#move.src().0 - r#move.dst().0).abs() == 2 && r#move.src().1 == r#move.dst().1;
+        if is_castling_attempt || matches!(r#move.kind(), MoveKind::KingCastle | MoveKind::QueenCastle) {
+            return IllegalMove::CastlingNotAllowed;
+        }
+
+        if self.empty_board_reach(r#move.src(), piece) & square_bit(r#move.dst()) != 0 {
+            IllegalMove::Blocked
+        } else {
+            IllegalMove::InvalidMovement
+        }
+    }
+
+    /// Squares the piece could move to if there were no other pieces, pawn captures only count onto occupied squares
+    fn empty_board_reach(&self, coord: Vector, piece: Piece) -> Bitboard {
+        match piece.piece_type() {
+            PieceType::Pawn => {
+                let (direction, start_rank) = if piece.color() == Color::White { (-1, 6) } else { (1, 1) };
+                // Pawns placed on the back rank can't push off the board
+                let push = coord + Vector(0, direction);
+                let mut reach = if push.is_on_board() { square_bit(push) } else { 0 };
+                if coord.1 == start_rank {
+                    reach |= square_bit(coord + Vector(0, 2 * direction));
+                }
+                reach | (pawn_attacks(coord, piece.color()) & self.occupancy())
+            }
+            PieceType::Knight => KNIGHT_ATTACKS[coord.index()],
+            PieceType::Bishop => bishop_attacks(coord, 0),
+            PieceType::Rook => rook_attacks(coord, 0),
+            PieceType::Queen => bishop_attacks(coord, 0) | rook_attacks(coord, 0),
+            PieceType::King => KING_ATTACKS[coord.index()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(fen: &str, src: Vector, dst: Vector, kind: MoveKind) -> Result<(), IllegalMove> {
+        Board::from_fen(fen).unwrap().validate_move(Move::new(src, dst, kind))
+    }
+
+    #[test]
+    fn illegal_move_reasons() {
+        let start = crate::chess::fen::START_POSITION_FEN;
+
+        assert_eq!(validate(start, Vector(4, 6), Vector(4, 4), MoveKind::DoublePawnPush), Ok(()));
+        assert_eq!(validate(start, Vector(4, 4), Vector(4, 3), MoveKind::Quiet), Err(IllegalMove::NoPiece(Vector(4, 4))));
+        assert_eq!(validate(start, Vector(4, 1), Vector(4, 3), MoveKind::DoublePawnPush), Err(IllegalMove::WrongSideToMove(Color::Black)));
+        assert_eq!(validate(start, Vector(0, 7), Vector(0, 5), MoveKind::Quiet), Err(IllegalMove::Blocked));
+        assert_eq!(validate(start, Vector(1, 7), Vector(1, 5), MoveKind::Quiet), Err(IllegalMove::InvalidMovement));
+        assert_eq!(validate(start, Vector(4, 6), Vector(4, 4), MoveKind::Quiet), Err(IllegalMove::WrongMoveKind { expected: MoveKind::DoublePawnPush }));
+
+        // The e2 knight is pinned by the rook on e8, castling through the attacked f1 isn't allowed
+        let pinned = "4r1k1/8/8/8/8/8/4N3/4K2R w K - 0 1";
+        assert_eq!(validate(pinned, Vector(4, 6), Vector(2, 5), MoveKind::Quiet), Err(IllegalMove::LeavesKingInCheck));
+        let castling_through_check = "5rk1/8/8/8/8/8/8/4K2R w K - 0 1";
+        assert_eq!(validate(castling_through_check, Vector(4, 7), Vector(6, 7), MoveKind::KingCastle), Err(IllegalMove::CastlingNotAllowed));
+
+        let promotion = "8/4P3/8/8/8/8/k7/4K3 w - - 0 1";
+        assert_eq!(validate(promotion, Vector(4, 1), Vector(4, 0), MoveKind::Quiet), Err(IllegalMove::MissingPromotionPiece));
+    }
+
+    #[test]
+    fn back_rank_pawn_moves_are_invalid() {
+        // FEN rejects such pawns, but they can still be placed directly
+        let mut board = Board::from_fen("k7/8/8/8/8/8/8/7K w - - 0 1").unwrap();
+        board.set_piece(Vector(1, 0), Some(Piece::new(Color::White, PieceType::Pawn)));
+
+        assert_eq!(board.validate_move(Move::new(Vector(1, 0), Vector(1, 2), MoveKind::Quiet)), Err(IllegalMove::InvalidMovement));
+    }
+
+    #[test]
+    fn illegal_moves_leave_board_untouched() {
+        let zobrist_table = ZobristTable::default();
+        let mut board = Board::default();
+
+        assert!(board.try_execute_move(Move::new(Vector(4, 6), Vector(4, 3), MoveKind::Quiet), &zobrist_table).is_err());
+        assert_eq!(board, Board::default());
+
+        assert!(board.try_execute_move(Move::new(Vector(6, 7), Vector(5, 5), MoveKind::Quiet), &zobrist_table).is_ok());
+        assert_eq!(board.next_player, Color::Black);
+    }
+}
@@ -1,5 +1,26 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
 use crate::chess::board::{BOARD_SIZE_X, BOARD_SIZE_Y};
 
+// Files are the x coordinate, ranks the y coordinate counted from Black's side, i.e. rank 8 is y = 0
+pub const FILE_A: i8 = 0;
+pub const FILE_B: i8 = 1;
+pub const FILE_C: i8 = 2;
+pub const FILE_D: i8 = 3;
+pub const FILE_E: i8 = 4;
+pub const FILE_F: i8 = 5;
+pub const FILE_G: i8 = 6;
+pub const FILE_H: i8 = 7;
+
+pub const RANK_8: i8 = 0;
+pub const RANK_7: i8 = 1;
+pub const RANK_6: i8 = 2;
+pub const RANK_5: i8 = 3;
+pub const RANK_4: i8 = 4;
+pub const RANK_3: i8 = 5;
+pub const RANK_2: i8 = 6;
+pub const RANK_1: i8 = 7;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Vector(pub i8, pub i8);
@@ -32,4 +53,99 @@ impl Vector {
     pub fn is_on_board(&self) -> bool {
         self.0 < BOARD_SIZE_X && self.1 < BOARD_SIZE_Y && self.0 >= 0 && self.1 >= 0
     }
-}
\ No newline at end of file
+
+    /// Square index used for bitboards, row by row starting at a8
+    #[inline]
+    pub const fn index(&self) -> usize {
+        (self.1 * BOARD_SIZE_X + self.0) as usize
+    }
+
+    #[inline]
+    pub const fn from_index(index: usize) -> Vector {
+        Vector(index as i8 % BOARD_SIZE_X, index as i8 / BOARD_SIZE_X)
+    }
+
+    /// File letter of the algebraic square name
+    pub fn file_char(&self) -> char {
+        (b'a' + self.0 as u8) as char
+    }
+
+    /// Rank digit of the algebraic square name
+    pub fn rank_char(&self) -> char {
+        (b'8' - self.1 as u8) as char
+    }
+
+    /// The same square seen from the other side of the board, e.g. e2 becomes e7
+    #[inline]
+    pub fn flip_rank(&self) -> Vector {
+        Vector(self.0, BOARD_SIZE_Y - 1 - self.1)
+    }
+
+    /// The square mirrored between the king and queen side, e.g. b1 becomes g1
+    #[inline]
+    pub fn flip_file(&self) -> Vector {
+        Vector(BOARD_SIZE_X - 1 - self.0, self.1)
+    }
+}
+
+/// Algebraic square name like "e4", only meaningful for coordinates on the board
+impl Display for Vector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.file_char(), self.rank_char())
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InvalidSquare(pub String);
+
+impl Display for InvalidSquare {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid square '{}', expected a file a-h followed by a rank 1-8", self.0)
+    }
+}
+
+impl std::error::Error for InvalidSquare {}
+
+impl FromStr for Vector {
+    type Err = InvalidSquare;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidSquare(name.to_owned());
+
+        let &[file, rank] = name.as_bytes() else {
+            return Err(invalid());
+        };
+        if !(b'a'..=b'h').contains(&file) || !(b'1'..=b'8').contains(&rank) {
+            return Err(invalid());
+        }
+
+        Ok(Vector((file - b'a') as i8, (b'8' - rank) as i8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn algebraic_square_names() {
+        assert_eq!(Vector(FILE_A, RANK_8).to_string(), "a8");
+        assert_eq!(Vector(FILE_E, RANK_4).to_string(), "e4");
+        assert_eq!("h1".parse(), Ok(Vector(FILE_H, RANK_1)));
+
+        for index in 0..64 {
+            let square = Vector::from_index(index);
+            assert_eq!(square.to_string().parse(), Ok(square));
+        }
+
+        for name in ["", "e", "e9", "i4", "e44", "E4"] {
+            assert_eq!(name.parse::<Vector>(), Err(InvalidSquare(name.to_owned())));
+        }
+    }
+
+    #[test]
+    fn flipped_squares() {
+        assert_eq!(Vector(FILE_E, RANK_2).flip_rank(), Vector(FILE_E, RANK_7));
+        assert_eq!(Vector(FILE_B, RANK_1).flip_file(), Vector(FILE_G, RANK_1));
+    }
+}
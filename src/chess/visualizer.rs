@@ -1,11 +1,16 @@
-use std::cmp::{max, min};
+use std::cmp::min;
 use crate::chess::board::{Board, Piece, PieceType};
+use crate::chess::chess960::CHESS960_POSITION_COUNT;
+use crate::chess::game::Game;
+use crate::chess::game_status::GameStatus;
 use crate::chess::negamax::negamax_move;
-use crate::chess::r#move::Move;
+use crate::chess::r#move::{Move, MoveKind};
+use crate::chess::move_list::MoveList;
+use crate::chess::validation::IllegalMove;
 use crate::chess::vector::Vector;
 use crate::chess::zobrist::ZobristTable;
 use egui::load::TexturePoll;
-use egui::{pos2, vec2, Color32, Frame, Key, PointerButton, Pos2, Rect, Shape, StrokeKind, TextureOptions, Vec2};
+use egui::{pos2, vec2, Align2, Color32, FontId, Frame, Key, PointerButton, Pos2, Rect, Shape, StrokeKind, TextureOptions, Vec2};
 use std::time::Instant;
 use egui::emath::OrderedFloat;
 use log::warn;
@@ -16,20 +21,24 @@ const SELECTION_COLOR: Color32 = Color32::from_rgb(50, 150, 168);
 const POSSIBLE_MOVE_COLOR: Color32 = Color32::from_rgb(209, 176, 56);
 const LAST_MOVE_COLOR: Color32 = Color32::from_rgb(90, 90, 90);
 const SUGGESTED_MOVE_COLOR: Color32 = Color32::from_rgb(118, 150, 72);
+const GAME_STATUS_BACKGROUND_COLOR: Color32 = Color32::from_rgba_premultiplied(0, 0, 0, 160);
 
 const INDICATOR_LINE_WIDTH: f32 = 6.0;
 
 const RECT_UV_ALL: Rect = Rect { min: pos2(0.0, 0.0), max: pos2(1.0, 1.0) };
 
+#[cfg(not(target_arch = "wasm32"))]
+const SAVED_GAME_PATH: &str = "game.pgn";
+
 
 
 
 pub struct ChessVisualizer {
     auto_move_enabled: bool,
-    board: Board,
-    move_index: u64,
+    game: Game,
+    status: GameStatus,
     selected_square: Option<Vector>,
-    possible_moves: Vec<Move>,
+    possible_moves: MoveList,
     suggested_move: Option<Move>,
     auto_move: DoubleTrigger,
     zobrist_table: ZobristTable,
@@ -42,10 +51,10 @@ impl Default for ChessVisualizer {
     fn default() -> Self {
         Self {
             auto_move_enabled: true,
-            board: Board::default(),
-            move_index: 0,
+            game: Game::default(),
+            status: GameStatus::Ongoing,
             selected_square: None,
-            possible_moves: vec![],
+            possible_moves: MoveList::new(),
             suggested_move: None,
             auto_move: DoubleTrigger::default(),
             zobrist_table: ZobristTable::default(),
@@ -91,7 +100,7 @@ impl eframe::App for ChessVisualizer {
 
 
                 // Paint pieces
-                for coord in self.board.coords() {
+                for coord in self.game.board().coords() {
                     let Vector(x, y) = coord;
 
                     let min = self.to_screen_space(coord);
@@ -103,7 +112,7 @@ impl eframe::App for ChessVisualizer {
                     painter.add(Shape::rect_filled(rect, 0, color));
 
                     // Paint piece
-                    if let Some(piece) = self.board.piece_at(coord) {
+                    if let Some(piece) = self.game.board().piece_at(coord) {
                         // try_load_texture does the caching for us (unlike load_texture)
                         let texture = ctx.try_load_texture(Self::piece_to_image_uri(piece).as_str(), TextureOptions::LINEAR, self.square_size.into()).expect("loading texture for piece");
 
@@ -116,23 +125,23 @@ impl eframe::App for ChessVisualizer {
                 }
 
                 // Paint last move
-                if let Some(Move { src, dst, .. }) = self.board.last_move {
-                    let src_pos = self.to_screen_space(src);
-                    let dst_pos = self.to_screen_space(dst);
+                if let Some(last_move) = self.game.board().last_move {
+                    let src_pos = self.to_screen_space(last_move.src());
+                    let dst_pos = self.to_screen_space(last_move.dst());
                     painter.add(Shape::rect_stroke(Rect::from_min_size(src_pos, self.square_size), 0.0, (INDICATOR_LINE_WIDTH, LAST_MOVE_COLOR), StrokeKind::Inside));
                     painter.add(Shape::rect_stroke(Rect::from_min_size(dst_pos, self.square_size), 0.0, (INDICATOR_LINE_WIDTH, LAST_MOVE_COLOR), StrokeKind::Inside));
                 }
 
                 // Paint possible moves
-                for Move { dst, .. } in self.possible_moves.iter() {
-                    let pos = self.to_screen_space(*dst);
+                for r#move in self.possible_moves.iter() {
+                    let pos = self.to_screen_space(r#move.dst());
                     painter.add(Shape::rect_stroke(Rect::from_min_size(pos, self.square_size), 0.0, (INDICATOR_LINE_WIDTH, POSSIBLE_MOVE_COLOR), StrokeKind::Inside));
                 }
 
                 // Paint suggestion
-                if let Some(Move { src, dst, .. }) = self.suggested_move {
-                    let src_pos = self.to_screen_space(src);
-                    let dst_pos = self.to_screen_space(dst);
+                if let Some(suggested_move) = self.suggested_move {
+                    let src_pos = self.to_screen_space(suggested_move.src());
+                    let dst_pos = self.to_screen_space(suggested_move.dst());
                     painter.add(Shape::rect_stroke(Rect::from_min_size(src_pos, self.square_size), 0.0, (INDICATOR_LINE_WIDTH, SUGGESTED_MOVE_COLOR), StrokeKind::Inside));
                     painter.add(Shape::rect_stroke(Rect::from_min_size(dst_pos, self.square_size), 0.0, (INDICATOR_LINE_WIDTH, SUGGESTED_MOVE_COLOR), StrokeKind::Inside));
                 }
@@ -144,6 +153,15 @@ impl eframe::App for ChessVisualizer {
                 }
 
 
+                // Paint game result
+                if self.status.is_over() {
+                    let board_rect = Rect::from_min_size(self.to_screen_space(Vector(0, 0)), self.square_size * 8.0);
+                    let text_rect = Rect::from_center_size(board_rect.center(), vec2(board_rect.width(), self.square_size.y));
+                    painter.add(Shape::rect_filled(text_rect, 0, GAME_STATUS_BACKGROUND_COLOR));
+                    painter.text(board_rect.center(), Align2::CENTER_CENTER, self.status.to_string(), FontId::proportional(self.square_size.y * 0.4), Color32::WHITE);
+                }
+
+
                 // Auto move
                 if self.auto_move.enabled() {
                     if self.auto_move.double_and_check() {
@@ -167,6 +185,26 @@ impl eframe::App for ChessVisualizer {
                 if ctx.input(|i| i.key_pressed(Key::Enter)) {
                     self.execute_suggested_move();
                 }
+                if ctx.input(|i| i.key_pressed(Key::ArrowLeft)) {
+                    self.navigate(Game::back);
+                }
+                if ctx.input(|i| i.key_pressed(Key::ArrowRight)) {
+                    self.navigate(Game::forward);
+                }
+                if ctx.input(|i| i.key_pressed(Key::S)) {
+                    self.save_game();
+                }
+                if ctx.input(|i| i.key_pressed(Key::N)) {
+                    self.new_game(Game::default());
+                }
+                if ctx.input(|i| i.key_pressed(Key::F)) {
+                    let index = rand::random_range(0..CHESS960_POSITION_COUNT);
+                    println!("Chess960 position {index}");
+
+                    let mut game = Game::new(Board::chess960(index));
+                    game.set_tag("Variant", "Chess960");
+                    self.new_game(game);
+                }
         });
     }
 }
@@ -180,38 +218,95 @@ impl ChessVisualizer {
         }
 
         if let Some(selected_square) = self.selected_square {
-            if let Some(r#move) = self.possible_moves.iter().filter(|m| m.dst == clicked_square).last() {
-                self.board.execute_move(r#move.clone(), &self.zobrist_table);
-
-                // println!("Evaluation: {:.2}, Zobrist Hash: {}", self.board.evaluate_position(), self.board.zobrist_hash);
-
-                self.suggested_move = None;
-
-                if self.auto_move_enabled {
-                    self.auto_move.initiate();
+            // Promotions default to a queen (the last generated), other clicks are validated to report why they can't be executed.
+            // Castling also works by clicking the rook, which is needed in Chess960 when the king keeps its square.
+            let board = self.game.board();
+            let r#move = self.possible_moves.iter().filter(|m| board.castling_rook_move(m).is_none()).rfind(|m| m.dst() == clicked_square)
+                .or_else(|| self.possible_moves.iter().find(|m| {
+                    m.dst() == clicked_square || board.castling_rook_move(m).is_some_and(|(rook_src, _)| rook_src == clicked_square)
+                }))
+                .copied()
+                .unwrap_or(Move::new(selected_square, clicked_square, MoveKind::Quiet));
+
+            // Clicking the selected square again just deselects it
+            if selected_square != clicked_square {
+                match self.try_execute_move(r#move) {
+                    Ok(()) => {
+                        // println!("Evaluation: {:.2}, Zobrist Hash: {}", self.game.board().evaluate_position(), self.game.board().zobrist_hash);
+
+                        self.suggested_move = None;
+
+                        if self.auto_move_enabled {
+                            self.auto_move.initiate();
+                        }
+                    }
+                    Err(reason) => warn!("Ignoring move: {reason}"),
                 }
             }
 
 
             self.selected_square = None;
-            self.possible_moves = vec![];
+            self.possible_moves = MoveList::new();
         } else {
             self.selected_square = Some(clicked_square);
 
-            if let Some(piece) = self.board.piece_at(clicked_square) {
-                if piece.color() == self.board.next_player {
-                    self.possible_moves = self.board.generate_piece_moves(clicked_square);
+            let board = self.game.board();
+            if let Some(piece) = board.piece_at(clicked_square) {
+                if piece.color() == board.next_player {
+                    self.possible_moves = board.generate_legal_piece_moves(clicked_square);
                 }
             }
         }
     }
 
+    fn try_execute_move(&mut self, r#move: Move) -> Result<(), IllegalMove> {
+        self.game.push_move(r#move, &self.zobrist_table)?;
+        self.status = self.game.status();
+
+        if self.status.is_over() {
+            println!("Game over: {}", self.status);
+        }
+
+        Ok(())
+    }
+
+    fn new_game(&mut self, game: Game) {
+        self.status = game.status();
+        self.game = game;
+        self.selected_square = None;
+        self.possible_moves = MoveList::new();
+        self.suggested_move = None;
+        self.auto_move = DoubleTrigger::default();
+    }
+
+    /// Steps through the game's moves, the next move played from there replaces the remaining ones
+    fn navigate(&mut self, step: fn(&mut Game, &ZobristTable) -> bool) {
+        if step(&mut self.game, &self.zobrist_table) {
+            self.status = self.game.status();
+            self.selected_square = None;
+            self.possible_moves = MoveList::new();
+            self.suggested_move = None;
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_game(&self) {
+        match std::fs::write(SAVED_GAME_PATH, self.game.to_pgn(&self.zobrist_table)) {
+            Ok(()) => println!("Saved game to {SAVED_GAME_PATH}"),
+            Err(error) => warn!("Couldn't save game to {SAVED_GAME_PATH}: {error}"),
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    fn save_game(&self) {
+        println!("{}", self.game.to_pgn(&self.zobrist_table));
+    }
+
     fn compute_suggestion(&mut self) {
 
         #[cfg(not(target_arch = "wasm32"))]
         let start = Instant::now();
 
-        if let Some((suggested_move, score)) = negamax_move(self.board.clone(), self.search_depth, &self.zobrist_table) {
+        if let Some((suggested_move, score)) = negamax_move(self.game.board().clone(), self.search_depth, &self.zobrist_table) {
             self.suggested_move = Some(suggested_move);
 
             #[cfg(not(target_arch = "wasm32"))]
@@ -227,7 +322,9 @@ impl ChessVisualizer {
 
     fn execute_suggested_move(&mut self) {
         if let Some(suggested_move) = self.suggested_move {
-            self.board.execute_move(suggested_move, &self.zobrist_table); // why can I pass ownership of the move if &mut self is used below?
+            if let Err(reason) = self.try_execute_move(suggested_move) { // why can I pass ownership of the move if &mut self is used below?
+                warn!("Suggested move is illegal: {reason}");
+            }
             self.suggested_move = None;
         } else {
             println!("No move being suggested");
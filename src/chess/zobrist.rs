@@ -1,8 +1,10 @@
 use rand::prelude::*;
 use rand_chacha::ChaCha20Rng;
-use crate::chess::board::Piece;
+use crate::chess::bitboard::pawn_attacks;
+use crate::chess::board::{Board, Color, Piece, PieceType};
+use crate::chess::r#move::MoveKind;
 use crate::chess::vector::Vector;
-// The hash of a new board is 0, preexisting piece are not taken into account
+// Boards are initialized with the full hash, moves then update it incrementally
 
 // white, black
 #[derive(Clone)]
@@ -58,4 +60,81 @@ impl ZobristTable {
     pub fn piece_key(&self, pos: &Vector, piece: &Piece) -> u64 {
         self.piece_keys[pos.1 as usize][pos.0 as usize][piece.piece_type().zobrist_index()][piece.color().zobrist_index()]
     }
-}
\ No newline at end of file
+}
+
+impl Board {
+    /// Hash of the position from scratch: pieces, player to move, castling rights and en passant file
+    pub fn compute_zobrist_hash(&self, zobrist_table: &ZobristTable) -> u64 {
+        let mut hash = 0;
+
+        for coord in self.coords_with_piece() {
+            hash ^= zobrist_table.piece_key(&coord, &self.piece_at(coord).unwrap());
+        }
+
+        if self.next_player == Color::Black {
+            hash ^= zobrist_table.black_to_move_key;
+        }
+
+        for color in [Color::White, Color::Black] {
+            if self.left_castling_rights[color.zobrist_index()] {
+                hash ^= zobrist_table.left_castle[color.zobrist_index()];
+            }
+            if self.right_castling_rights[color.zobrist_index()] {
+                hash ^= zobrist_table.right_castle[color.zobrist_index()];
+            }
+        }
+
+        if let Some(file) = self.en_passant_hash_file() {
+            hash ^= zobrist_table.en_passant_file[file as usize];
+        }
+
+        hash
+    }
+
+    /// File of the en passant square if a pawn of the player to move could capture there, ignoring pins.
+    /// Positions where nobody can capture en passant are the same under the repetition rules, so they must hash equally.
+    pub(crate) fn en_passant_hash_file(&self) -> Option<i8> {
+        let last_move = self.last_move.filter(|last_move| last_move.kind() == MoveKind::DoublePawnPush)?;
+        let en_passant_square = Vector(last_move.src().0, (last_move.src().1 + last_move.dst().1) / 2);
+
+        let capturing_pawns = pawn_attacks(en_passant_square, self.next_player.other()) & self.piece_bitboard(PieceType::Pawn, self.next_player);
+        (capturing_pawns != 0).then_some(en_passant_square.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::r#move::Move;
+
+    #[test]
+    fn transpositions_hash_equally() {
+        let zobrist_table = ZobristTable::default();
+
+        // 1. Nf3 Nf6 2. Nc3 and 1. Nc3 Nf6 2. Nf3
+        let mut board = Board::default();
+        board.execute_move(Move::new(Vector(6, 7), Vector(5, 5), MoveKind::Quiet), &zobrist_table);
+        board.execute_move(Move::new(Vector(6, 0), Vector(5, 2), MoveKind::Quiet), &zobrist_table);
+        board.execute_move(Move::new(Vector(1, 7), Vector(2, 5), MoveKind::Quiet), &zobrist_table);
+
+        let from_fen = Board::from_fen("rnbqkb1r/pppppppp/5n2/8/8/2N2N2/PPPPPPPP/R1BQKB1R b KQkq - 3 2").unwrap();
+        assert_eq!(board.zobrist_hash, from_fen.zobrist_hash);
+        assert_ne!(board.zobrist_hash, Board::default().zobrist_hash);
+    }
+
+    #[test]
+    fn en_passant_only_hashed_when_capturable() {
+        let zobrist_table = ZobristTable::default();
+
+        // Nobody can capture on e3, so the double push reaches the same position as the FEN without en passant square
+        let mut board = Board::default();
+        board.execute_move(Move::new(Vector(4, 6), Vector(4, 4), MoveKind::DoublePawnPush), &zobrist_table);
+        let without_en_passant = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(board.zobrist_hash, without_en_passant.zobrist_hash);
+
+        // The pawn on d4 can capture on e3
+        let capturable = Board::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        let not_capturable = Board::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_ne!(capturable.zobrist_hash, not_capturable.zobrist_hash);
+    }
+}
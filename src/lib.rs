@@ -0,0 +1,28 @@
+//! Chess engine with legal move generation, evaluation and a parallel negamax search, usable without the GUI
+
+mod chess;
+
+pub use chess::{bitboard, board, chess960, epd, evaluation, fen, game, game_status, r#move, move_list, negamax, perft, pgn, san, uci, validation, vector, zobrist, MoveUndo};
+#[cfg(feature = "gui")]
+pub use chess::visualizer;
+
+// The types most users need, without going through the modules above
+pub use chess::board::{Board, CastlingFiles, Color, Piece, PieceType};
+pub use chess::r#move::{Move, MoveKind};
+pub use chess::move_list::MoveList;
+pub use chess::vector::{InvalidSquare, Vector};
+pub use chess::vector::{FILE_A, FILE_B, FILE_C, FILE_D, FILE_E, FILE_F, FILE_G, FILE_H};
+pub use chess::vector::{RANK_1, RANK_2, RANK_3, RANK_4, RANK_5, RANK_6, RANK_7, RANK_8};
+pub use chess::fen::{FenError, START_POSITION_FEN};
+pub use chess::san::SanError;
+pub use chess::uci::UciMoveError;
+pub use chess::pgn::{parse_pgn, PgnError};
+pub use chess::chess960::{CHESS960_POSITION_COUNT, STANDARD_POSITION_INDEX};
+pub use chess::game::{Annotation, Game, GameMove, Variation};
+pub use chess::game_status::{DrawReason, GameResult, GameStatus};
+pub use chess::validation::IllegalMove;
+pub use chess::zobrist::ZobristTable;
+pub use chess::negamax::{negamax_move, negamax_move_until};
+pub use chess::epd::{parse_epd, EpdError, EpdRecord};
+#[cfg(feature = "gui")]
+pub use chess::visualizer::ChessVisualizer;
@@ -0,0 +1,70 @@
+use chess_rs::{Board, ZobristTable, START_POSITION_FEN};
+
+// Reference node counts from https://www.chessprogramming.org/Perft_Results
+
+fn assert_perft(fen: &str, expected_by_depth: &[u64]) {
+    let board = Board::from_fen(fen).unwrap();
+    let zobrist_table = ZobristTable::default();
+
+    for (depth, expected) in (1..).zip(expected_by_depth) {
+        assert_eq!(board.perft(depth, &zobrist_table), *expected, "perft({depth}) of {fen}");
+    }
+}
+
+#[test]
+fn start_position() {
+    assert_perft(START_POSITION_FEN, &[20, 400, 8902, 197281]);
+}
+
+#[test]
+fn kiwipete() {
+    assert_perft("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862]);
+}
+
+#[test]
+fn en_passant_discovered_checks() {
+    assert_perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238, 674624]);
+}
+
+#[test]
+fn promotions_and_castling_rights() {
+    assert_perft("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467, 422333]);
+    assert_perft("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1", &[6, 264, 9467, 422333]); // mirrored
+}
+
+#[test]
+fn promotion_with_check() {
+    assert_perft("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379]);
+}
+
+#[test]
+fn middlegame() {
+    assert_perft("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2079, 89890]);
+}
+
+#[test]
+fn chess960() {
+    // Reference node counts from https://www.chessprogramming.org/Chess960_Perft_Results
+    assert_perft("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", &[21, 528, 12189, 326672]);
+    assert_perft("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", &[21, 807, 18002, 667366]);
+    assert_perft("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", &[20, 479, 10471, 273318]);
+}
+
+#[test]
+fn divide_sums_to_perft() {
+    let board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    let zobrist_table = ZobristTable::default();
+
+    let divided = board.divide(3, &zobrist_table);
+    assert_eq!(divided.len(), 48);
+    assert_eq!(divided.iter().map(|(_, nodes)| nodes).sum::<u64>(), board.perft(3, &zobrist_table));
+}
+
+#[test]
+fn parallel_and_cached_perft_match() {
+    let board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    let zobrist_table = ZobristTable::default();
+
+    assert_eq!(board.perft_parallel(3, &zobrist_table, false), 97862);
+    assert_eq!(board.perft_parallel(4, &zobrist_table, true), 4085603);
+}